@@ -0,0 +1,72 @@
+// Times the collision broad phase and a full simulation step with thousands of
+// small balls bouncing around the view.
+//
+//     cargo run --release --example collision_bench -- 4000
+
+extern crate ecs;
+extern crate rand;
+extern crate rustecspong;
+
+use ecs::*;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use rustecspong::Options;
+use rustecspong::ecs::arena::Arena;
+use rustecspong::ecs::broadphase::{Bounds, Grid};
+use rustecspong::ecs::components::*;
+use rustecspong::ecs::scaffold::Components;
+use rustecspong::headless::{self, Headless};
+use std::time::Instant;
+
+const TICKS: u32 = 240;
+
+fn millis(start: Instant) -> f64 {
+    let d = start.elapsed();
+    d.as_secs() as f64 * 1000.0 + d.subsec_nanos() as f64 / 1_000_000.0
+}
+
+fn main() {
+    let n: usize = std::env::args().nth(1).and_then(|a| a.parse().ok()).unwrap_or(2000);
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let arena = Arena::default();
+    let (view_w, view_h) = (arena.view_w(), arena.view_h());
+
+    let bounds: Vec<Bounds> = (0..n).map(|_| {
+        let p = Position { x: rng.gen_range(0.0, view_w), y: rng.gen_range(0.0, view_h) };
+        Bounds::of(&p, &ShapeVariant::Circle(3.0))
+    }).collect();
+
+    let start = Instant::now();
+    let mut brute = 0;
+    for i in 0..n {
+        for j in i + 1..n {
+            if bounds[i].overlaps(&bounds[j]) {
+                brute += 1;
+            }
+        }
+    }
+    println!("brute force: {} pairs in {:.2}ms", brute, millis(start));
+
+    let mut grid = Grid::new(64.0);
+    let start = Instant::now();
+    let pairs = grid.pairs(&bounds, |_, _| true);
+    println!("grid:        {} pairs in {:.2}ms", pairs.len(), millis(start));
+
+    let options = Options { seed: Some(1), ..Options::default() };
+    let mut sim = Headless::new(headless::DEFAULT_DT, &options);
+    for _ in 0..n {
+        let (x, y) = (rng.gen_range(10.0, view_w - 10.0), rng.gen_range(10.0, view_h - 10.0));
+        let (vx, vy) = (rng.gen_range(-200.0, 200.0), rng.gen_range(-200.0, 200.0));
+        sim.world.create_entity(|entity: BuildData<Components>, data: &mut Components| {
+            data.positions.add(&entity, Position { x: x, y: y });
+            data.velocities.add(&entity, Velocity { x: vx, y: vy });
+            data.shapes.add(&entity, Shape { variant: ShapeVariant::Circle(3.0), border: None });
+            data.clamps.add(&entity, WindowClamp { variant: ClampVariant::Bounce });
+            data.collision_layers.add(&entity, CollisionLayer::gameplay());
+        });
+    }
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        sim.step();
+    }
+    println!("{} entities: {:.2}ms per step", n, millis(start) / TICKS as f64);
+}
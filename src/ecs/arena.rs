@@ -0,0 +1,138 @@
+use std::path::Path;
+use ecs::components::WinCondition;
+use files;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Wall {
+    Bounce,
+    Goal
+}
+
+impl ::std::str::FromStr for Wall {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Wall, String> {
+        match s {
+            "bounce" => Ok(Wall::Bounce),
+            "goal" => Ok(Wall::Goal),
+            _ => Err(format!("Unknown wall '{}', expected bounce or goal", s))
+        }
+    }
+}
+
+/// The court every system agrees on. The view is the playable area inside
+/// the window's padding, with (0, 0) at its top left.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Arena {
+    pub window_w: f64,
+    pub window_h: f64,
+    pub padding: f64,
+    pub left: Wall,
+    pub right: Wall,
+    pub goal_size: f64, // Height of the opening in a goal wall, 0 for the whole wall.
+    pub disp_fudge: f64, // Extra distance things are pushed clear of walls.
+    pub win: WinCondition // What a new game is played to.
+}
+
+impl Default for Arena {
+    fn default() -> Arena {
+        Arena {
+            window_w: 800.0,
+            window_h: 600.0,
+            padding: 40.0,
+            left: Wall::Goal,
+            right: Wall::Goal,
+            goal_size: 0.0,
+            disp_fudge: 5.0,
+            win: WinCondition::default()
+        }
+    }
+}
+
+// Every key is optional, anything left out keeps its default.
+#[derive(RustcDecodable)]
+struct ArenaFile {
+    window_w: Option<f64>,
+    window_h: Option<f64>,
+    padding: Option<f64>,
+    left: Option<String>,
+    right: Option<String>,
+    goal_size: Option<f64>,
+    disp_fudge: Option<f64>,
+    first_to: Option<u32>,
+    win_by: Option<u32>
+}
+
+impl Arena {
+    pub fn view_w(&self) -> f64 { self.window_w - 2.0 * self.padding }
+    pub fn view_h(&self) -> f64 { self.window_h - 2.0 * self.padding }
+
+    /// Whether something at height `y` crossing a `wall` goes in the goal.
+    pub fn in_goal(&self, wall: Wall, y: f64) -> bool {
+        wall == Wall::Goal
+        && (self.goal_size <= 0.0 || (y - self.view_h() / 2.0).abs() <= self.goal_size / 2.0)
+    }
+
+    pub fn parse(src: &str) -> Result<Arena, String> {
+        let file: ArenaFile = try!(files::decode_toml(src));
+
+        let d = Arena::default();
+        let wall = |w: Option<String>, default| match w {
+            Some(w) => w.parse(),
+            None => Ok(default)
+        };
+        let arena = Arena {
+            window_w: file.window_w.unwrap_or(d.window_w),
+            window_h: file.window_h.unwrap_or(d.window_h),
+            padding: file.padding.unwrap_or(d.padding),
+            left: try!(wall(file.left, d.left)),
+            right: try!(wall(file.right, d.right)),
+            goal_size: file.goal_size.unwrap_or(d.goal_size),
+            disp_fudge: file.disp_fudge.unwrap_or(d.disp_fudge),
+            win: try!(WinCondition::new(file.first_to.unwrap_or(d.win.first_to),
+                                        file.win_by.unwrap_or(d.win.win_by)))
+        };
+        if arena.view_w() <= 0.0 || arena.view_h() <= 0.0 {
+            return Err(format!("Padding of {} leaves no room in a {}x{} window",
+                               arena.padding, arena.window_w, arena.window_h));
+        }
+        Ok(arena)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Arena, String> {
+        files::load(path.as_ref(), Arena::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_is_the_default() {
+        assert_eq!(Arena::parse(""), Ok(Arena::default()));
+    }
+
+    #[test]
+    fn keys_override_defaults() {
+        let arena = Arena::parse("padding = 10.0\nleft = \"bounce\"\nfirst_to = 5\n").unwrap();
+        assert_eq!(arena.padding, 10.0);
+        assert_eq!(arena.left, Wall::Bounce);
+        assert_eq!(arena.right, Wall::Goal);
+        assert_eq!(arena.win.first_to, 5);
+        assert_eq!(arena.win.win_by, Arena::default().win.win_by);
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(Arena::parse("left = \"open\"").unwrap_err().contains("Unknown wall 'open'"));
+        assert!(Arena::parse("padding = 400.0").unwrap_err().contains("leaves no room"));
+        assert!(Arena::parse("first_to = 0").is_err());
+        assert!(Arena::parse("win_by = 0").is_err());
+        assert!(Arena::parse("padding = \"wide\"").is_err());
+    }
+
+    #[test]
+    fn syntax_errors_have_a_position() {
+        assert!(Arena::parse("padding = 10.0\nleft = ").unwrap_err().starts_with("2:"));
+    }
+}
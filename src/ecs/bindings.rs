@@ -0,0 +1,111 @@
+use std::path::Path;
+use ecs::components::{Axis, Binding, PlayerController};
+use ecs::prefab::{Prefab, Prefabs};
+use files;
+
+/// Controls for each paddle, replacing the bindings in its prefab while
+/// keeping its acceleration. A side left out of the file keeps its prefab's.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Bindings {
+    pub left: Option<PlayerController>,
+    pub right: Option<PlayerController>
+}
+
+#[derive(RustcDecodable)]
+struct SideFile {
+    up: Option<Vec<String>>,
+    down: Option<Vec<String>>,
+    axis: Option<String>
+}
+
+#[derive(RustcDecodable)]
+struct BindingsFile {
+    left: Option<SideFile>,
+    right: Option<SideFile>
+}
+
+fn controller(side: SideFile) -> Result<PlayerController, String> {
+    let bindings = |names: Option<Vec<String>>| -> Result<Vec<Binding>, String> {
+        names.unwrap_or(Vec::new()).iter().map(|b| b.parse()).collect()
+    };
+    Ok(PlayerController {
+        up: try!(bindings(side.up)),
+        down: try!(bindings(side.down)),
+        axis: match side.axis {
+            Some(a) => Some(try!(a.parse::<Axis>())),
+            None => None
+        },
+        accel: None,
+        decel: None
+    })
+}
+
+impl Bindings {
+    pub fn parse(src: &str) -> Result<Bindings, String> {
+        let file: BindingsFile = try!(files::decode_toml(src));
+        Ok(Bindings {
+            left: match file.left { Some(side) => Some(try!(controller(side))), None => None },
+            right: match file.right { Some(side) => Some(try!(controller(side))), None => None }
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bindings, String> {
+        files::load(path.as_ref(), Bindings::parse)
+    }
+
+    pub fn apply(&self, prefabs: &mut Prefabs) {
+        let rebind = |prefab: &mut Prefab, bindings: &Option<PlayerController>| {
+            if let Some(ref bindings) = *bindings {
+                let mut controller = bindings.clone();
+                if let Some(ref old) = prefab.player_controller {
+                    controller.accel = old.accel;
+                    controller.decel = old.decel;
+                }
+                prefab.player_controller = Some(controller);
+            }
+        };
+        rebind(&mut prefabs.left_paddle, &self.left);
+        rebind(&mut prefabs.right_paddle, &self.right);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::keyboard::Key;
+    use piston::input::mouse::MouseButton;
+
+    #[test]
+    fn parses_each_side() {
+        let bindings = Bindings::parse("[left]\nup = [\"W\", \"pad0:button9\"]\ndown = [\"mouse:Left\"]\naxis = \"-pad0:axis1\"\n").unwrap();
+        let left = bindings.left.unwrap();
+        assert_eq!(left.up, vec![Binding::Key(Key::W), Binding::Pad { id: 0, button: 9 }]);
+        assert_eq!(left.down, vec![Binding::Mouse(MouseButton::Left)]);
+        assert_eq!(left.axis, Some(Axis::Pad { id: 0, axis: 1, invert: true }));
+        assert_eq!(bindings.right, None);
+    }
+
+    #[test]
+    fn rejects_bad_bindings() {
+        assert!(Bindings::parse("[left]\nup = [\"pad0:axis1\"]\n").is_err());
+        assert!(Bindings::parse("[left]\naxis = \"pad0:button1\"\n").is_err());
+        assert!(Bindings::parse("[left]\nup = \"W\"\n").is_err());
+    }
+
+    #[test]
+    fn apply_keeps_the_prefab_acceleration() {
+        let mut prefabs = Prefabs::default();
+        {
+            let old = prefabs.left_paddle.player_controller.as_mut().unwrap();
+            old.accel = Some(3000.0);
+            old.decel = Some(6000.0);
+        }
+        let right = prefabs.right_paddle.player_controller.clone();
+        Bindings::parse("[left]\nup = [\"I\"]\ndown = [\"K\"]\n").unwrap().apply(&mut prefabs);
+        let left = prefabs.left_paddle.player_controller.unwrap();
+        assert_eq!(left.up, vec![Binding::Key(Key::I)]);
+        assert_eq!(left.down, vec![Binding::Key(Key::K)]);
+        assert_eq!((left.accel, left.decel), (Some(3000.0), Some(6000.0)));
+        assert_eq!(prefabs.right_paddle.player_controller, right);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use ecs::components::{Position, ShapeVariant};
+use ecs::components::ShapeVariant::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bounds {
+    pub min: [f64; 2],
+    pub max: [f64; 2]
+}
+
+impl Bounds {
+    pub fn of(p: &Position, shape: &ShapeVariant) -> Bounds {
+        match *shape {
+            Point => Bounds { min: [p.x, p.y], max: [p.x, p.y] },
+            Circle(r) => Bounds { min: [p.x - r, p.y - r], max: [p.x + r, p.y + r] },
+            Square(w, h) => Bounds { min: [p.x - w, p.y - h], max: [p.x + w, p.y + h] },
+            Line(l) => Bounds {
+                min: [l[0].min(l[2]), l[1].min(l[3])],
+                max: [l[0].max(l[2]), l[1].max(l[3])]
+            }
+        }
+    }
+
+    /// Grows the bounds to also cover wherever they get moved to by `by`.
+    pub fn swept(self, by: [f64; 2]) -> Bounds {
+        Bounds {
+            min: [self.min[0].min(self.min[0] + by[0]), self.min[1].min(self.min[1] + by[1])],
+            max: [self.max[0].max(self.max[0] + by[0]), self.max[1].max(self.max[1] + by[1])]
+        }
+    }
+
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        self.min[0] <= other.max[0] && other.min[0] <= self.max[0]
+        && self.min[1] <= other.max[1] && other.min[1] <= self.max[1]
+    }
+}
+
+/// Uniform grid over world space. Each bounds is filed under every cell it
+/// touches, and only things sharing a cell are ever compared.
+pub struct Grid {
+    pub cell: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>
+}
+
+impl Grid {
+    pub fn new(cell: f64) -> Grid {
+        Grid {
+            cell: cell,
+            cells: HashMap::new()
+        }
+    }
+
+    fn cell_of(&self, v: f64) -> i64 {
+        (v / self.cell).floor() as i64
+    }
+
+    /// Index pairs `(i, j)` with `i < j` whose bounds overlap and that
+    /// `filter` accepts, each reported once and in sorted order so results
+    /// don't depend on hashing.
+    pub fn pairs<F>(&mut self, bounds: &[Bounds], filter: F) -> Vec<(usize, usize)>
+        where F: Fn(usize, usize) -> bool {
+        for v in self.cells.values_mut() {
+            v.clear();
+        }
+        for (i, b) in bounds.iter().enumerate() {
+            for cx in self.cell_of(b.min[0])..self.cell_of(b.max[0]) + 1 {
+                for cy in self.cell_of(b.min[1])..self.cell_of(b.max[1]) + 1 {
+                    self.cells.entry((cx, cy)).or_insert_with(Vec::new).push(i);
+                }
+            }
+        }
+        let mut pairs = Vec::new();
+        for (&(cx, cy), members) in self.cells.iter() {
+            for (k, &i) in members.iter().enumerate() {
+                for &j in members[k + 1..].iter() {
+                    let (a, b) = (&bounds[i], &bounds[j]);
+                    if !a.overlaps(b) || !filter(i, j) {
+                        continue;
+                    }
+                    // Pairs spanning several cells are only reported from the
+                    // cell holding the corner of their overlap.
+                    let corner = [a.min[0].max(b.min[0]), a.min[1].max(b.min[1])];
+                    if self.cell_of(corner[0]) == cx && self.cell_of(corner[1]) == cy {
+                        pairs.push(if i < j { (i, j) } else { (j, i) });
+                    }
+                }
+            }
+        }
+        // Empty cells would otherwise pile up as things move around.
+        self.cells.retain(|_, v| !v.is_empty());
+        pairs.sort();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn b(min: [f64; 2], max: [f64; 2]) -> Bounds {
+        Bounds { min: min, max: max }
+    }
+
+    #[test]
+    fn pairs_spanning_cells_are_reported_once() {
+        let mut grid = Grid::new(10.0);
+        let bounds = [b([0.0, 0.0], [25.0, 25.0]), b([5.0, 5.0], [30.0, 30.0]), b([-15.0, -15.0], [1.0, 1.0])];
+        assert_eq!(grid.pairs(&bounds, |_, _| true), vec![(0, 1), (0, 2)]);
+        // Cells are reused between updates.
+        assert_eq!(grid.pairs(&bounds, |_, _| true), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn sharing_a_cell_is_not_enough() {
+        let mut grid = Grid::new(10.0);
+        let bounds = [b([0.0, 0.0], [1.0, 1.0]), b([2.0, 2.0], [3.0, 3.0])];
+        assert_eq!(grid.pairs(&bounds, |_, _| true), vec![]);
+    }
+
+    #[test]
+    fn filter_and_order() {
+        let mut grid = Grid::new(4.0);
+        let bounds = [
+            b([0.0, 0.0], [10.0, 10.0]),
+            b([1.0, 1.0], [2.0, 2.0]),
+            b([8.0, 8.0], [9.0, 9.0]),
+            b([-3.0, 5.0], [1.0, 6.0])
+        ];
+        assert_eq!(grid.pairs(&bounds, |_, _| true), vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(grid.pairs(&bounds, |i, j| i + j != 2), vec![(0, 1), (0, 3)]);
+    }
+
+    #[test]
+    fn swept_bounds_cover_both_ends() {
+        let moved = b([0.0, 0.0], [1.0, 1.0]).swept([3.0, -2.0]);
+        assert_eq!(moved, b([0.0, -2.0], [4.0, 1.0]));
+    }
+}
@@ -0,0 +1,352 @@
+use vecmath::*;
+use ecsrs::Entity;
+use ecs::components::{Position, ShapeVariant};
+use ecs::components::ShapeVariant::*;
+
+/// Published by CollisionSystem into `Services::collisions` for every pair it
+/// resolved this update. `normal` points from `a` to `b`. Swept hits caught
+/// before they overlapped have a `depth` of zero.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub normal: Vector2<f64>,
+    pub depth: f64,
+    pub point: Vector2<f64>
+}
+
+/// How two overlapping shapes touch. `normal` is a unit vector pointing from
+/// the first shape towards the second, and moving the second shape `depth`
+/// along it (or the first back by the same) separates them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Contact {
+    pub normal: Vector2<f64>,
+    pub depth: f64,
+    pub point: Vector2<f64>
+}
+
+impl Contact {
+    pub fn flipped(self) -> Contact {
+        Contact {
+            normal: vec2_neg(self.normal),
+            depth: self.depth,
+            point: self.point
+        }
+    }
+}
+
+// Lines are stored in world coordinates, every other shape is centered on its
+// position. Squares hold half extents.
+pub fn contact(pa: &Position, a: &ShapeVariant, pb: &Position, b: &ShapeVariant) -> Option<Contact> {
+    let ca = [pa.x, pa.y];
+    let cb = [pb.x, pb.y];
+    match (a, b) {
+        (&Point, &Point) => None,
+        (&Point, &Circle(r)) => circle_circle(ca, 0.0, cb, r),
+        (&Circle(r), &Point) => circle_circle(ca, r, cb, 0.0),
+        (&Circle(r1), &Circle(r2)) => circle_circle(ca, r1, cb, r2),
+
+        (&Square(w, h), &Circle(r)) => aabb_circle(ca, [w, h], cb, r),
+        (&Circle(r), &Square(w, h)) => aabb_circle(cb, [w, h], ca, r).map(Contact::flipped),
+        (&Square(w, h), &Point) => aabb_circle(ca, [w, h], cb, 0.0),
+        (&Point, &Square(w, h)) => aabb_circle(cb, [w, h], ca, 0.0).map(Contact::flipped),
+        (&Square(w1, h1), &Square(w2, h2)) => aabb_aabb(ca, [w1, h1], cb, [w2, h2]),
+
+        (&Line(l), &Circle(r)) => segment_circle(l, cb, r),
+        (&Circle(r), &Line(l)) => segment_circle(l, ca, r).map(Contact::flipped),
+        (&Line(l), &Square(w, h)) => segment_aabb(l, cb, [w, h]),
+        (&Square(w, h), &Line(l)) => segment_aabb(l, ca, [w, h]).map(Contact::flipped),
+        // Points have no area to hit a line with, and lines only ever
+        // block other shapes.
+        (&Line(_), _) | (_, &Line(_)) => None
+    }
+}
+
+pub fn circle_circle(ca: Vector2<f64>, ra: f64, cb: Vector2<f64>, rb: f64) -> Option<Contact> {
+    let d = vec2_sub(cb, ca);
+    let dist2 = vec2_square_len(d);
+    if dist2 >= (ra + rb) * (ra + rb) {
+        return None;
+    }
+    let dist = dist2.sqrt();
+    let normal = if dist > 0.0 { vec2_scale(d, 1.0 / dist) } else { [1.0, 0.0] };
+    Some(Contact {
+        normal: normal,
+        depth: ra + rb - dist,
+        point: vec2_add(ca, vec2_scale(normal, ra))
+    })
+}
+
+pub fn aabb_circle(cb: Vector2<f64>, half: Vector2<f64>, cc: Vector2<f64>, r: f64) -> Option<Contact> {
+    let closest = [
+        cc[0].max(cb[0] - half[0]).min(cb[0] + half[0]),
+        cc[1].max(cb[1] - half[1]).min(cb[1] + half[1])
+    ];
+    let d = vec2_sub(cc, closest);
+    let dist2 = vec2_square_len(d);
+    if dist2 > 0.0 {
+        if dist2 >= r * r {
+            return None;
+        }
+        let dist = dist2.sqrt();
+        return Some(Contact {
+            normal: vec2_scale(d, 1.0 / dist),
+            depth: r - dist,
+            point: closest
+        });
+    }
+    // Center is inside the box, push out through the nearest face.
+    let rel = vec2_sub(cc, cb);
+    let to_x = half[0] - rel[0].abs();
+    let to_y = half[1] - rel[1].abs();
+    let (normal, depth, point) = if to_x < to_y {
+        let s = if rel[0] < 0.0 { -1.0 } else { 1.0 };
+        ([s, 0.0], r + to_x, [cb[0] + s * half[0], cc[1]])
+    } else {
+        let s = if rel[1] < 0.0 { -1.0 } else { 1.0 };
+        ([0.0, s], r + to_y, [cc[0], cb[1] + s * half[1]])
+    };
+    Some(Contact { normal: normal, depth: depth, point: point })
+}
+
+pub fn aabb_aabb(ca: Vector2<f64>, ha: Vector2<f64>, cb: Vector2<f64>, hb: Vector2<f64>) -> Option<Contact> {
+    let d = vec2_sub(cb, ca);
+    let ox = ha[0] + hb[0] - d[0].abs();
+    let oy = ha[1] + hb[1] - d[1].abs();
+    if ox <= 0.0 || oy <= 0.0 {
+        return None;
+    }
+    // Middle of the overlapping region.
+    let point = [
+        ((ca[0] - ha[0]).max(cb[0] - hb[0]) + (ca[0] + ha[0]).min(cb[0] + hb[0])) / 2.0,
+        ((ca[1] - ha[1]).max(cb[1] - hb[1]) + (ca[1] + ha[1]).min(cb[1] + hb[1])) / 2.0
+    ];
+    if ox < oy {
+        let s = if d[0] < 0.0 { -1.0 } else { 1.0 };
+        Some(Contact { normal: [s, 0.0], depth: ox, point: point })
+    } else {
+        let s = if d[1] < 0.0 { -1.0 } else { 1.0 };
+        Some(Contact { normal: [0.0, s], depth: oy, point: point })
+    }
+}
+
+pub fn closest_on_segment(l: [f64; 4], p: Vector2<f64>) -> Vector2<f64> {
+    let a = [l[0], l[1]];
+    let ab = vec2_sub([l[2], l[3]], a);
+    let len2 = vec2_square_len(ab);
+    if len2 == 0.0 {
+        return a;
+    }
+    let t = (vec2_dot(vec2_sub(p, a), ab) / len2).max(0.0).min(1.0);
+    vec2_add(a, vec2_scale(ab, t))
+}
+
+pub fn segment_circle(l: [f64; 4], cc: Vector2<f64>, r: f64) -> Option<Contact> {
+    let closest = closest_on_segment(l, cc);
+    let d = vec2_sub(cc, closest);
+    let dist2 = vec2_square_len(d);
+    if dist2 >= r * r {
+        return None;
+    }
+    let dist = dist2.sqrt();
+    let normal = if dist > 0.0 {
+        vec2_scale(d, 1.0 / dist)
+    } else {
+        // Center sits on the line, pick the segment's left hand normal.
+        let along = vec2_sub([l[2], l[3]], [l[0], l[1]]);
+        let len = vec2_len(along);
+        if len > 0.0 { [-along[1] / len, along[0] / len] } else { [1.0, 0.0] }
+    };
+    Some(Contact { normal: normal, depth: r - dist, point: closest })
+}
+
+// Separating axis test on the box axes and the segment normal.
+pub fn segment_aabb(l: [f64; 4], cb: Vector2<f64>, half: Vector2<f64>) -> Option<Contact> {
+    let a = [l[0], l[1]];
+    let b = [l[2], l[3]];
+    let along = vec2_sub(b, a);
+    let len = vec2_len(along);
+    let mut axes = vec![[1.0, 0.0], [0.0, 1.0]];
+    if len > 0.0 {
+        axes.push([-along[1] / len, along[0] / len]);
+    }
+    let mut best: Option<(Vector2<f64>, f64)> = None;
+    for axis in axes {
+        let (s1, s2) = (vec2_dot(a, axis), vec2_dot(b, axis));
+        let (smin, smax) = (s1.min(s2), s1.max(s2));
+        let c = vec2_dot(cb, axis);
+        let extent = half[0] * axis[0].abs() + half[1] * axis[1].abs();
+        // How far the box has to move along +axis or -axis to clear the segment.
+        let forward = smax - (c - extent);
+        let back = (c + extent) - smin;
+        if forward <= 0.0 || back <= 0.0 {
+            return None;
+        }
+        let (n, depth) = if forward < back { (axis, forward) } else { (vec2_neg(axis), back) };
+        if best.map_or(true, |(_, d)| depth < d) {
+            best = Some((n, depth));
+        }
+    }
+    best.map(|(normal, depth)| {
+        let closest = closest_on_segment(l, cb);
+        Contact {
+            normal: normal,
+            depth: depth,
+            point: [
+                closest[0].max(cb[0] - half[0]).min(cb[0] + half[0]),
+                closest[1].max(cb[1] - half[1]).min(cb[1] + half[1])
+            ]
+        }
+    })
+}
+
+/// Earliest time, as a fraction of `motion`, at which a circle of radius `r`
+/// moving from `start` by `motion` touches a box, along with the contact
+/// normal pointing from the box to the circle. None if it misses, or if it
+/// already overlaps at `start` since `contact` handles that case.
+pub fn sweep_circle_aabb(start: Vector2<f64>, motion: Vector2<f64>, r: f64, cb: Vector2<f64>, half: Vector2<f64>) -> Option<(f64, Vector2<f64>)> {
+    // Ray against the box grown by the radius...
+    let grown = [half[0] + r, half[1] + r];
+    let mut t_near = ::std::f64::NEG_INFINITY;
+    let mut t_far = ::std::f64::INFINITY;
+    let mut near_axis = 0;
+    for k in 0..2 {
+        if motion[k] == 0.0 {
+            if (start[k] - cb[k]).abs() >= grown[k] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (cb[k] - grown[k] - start[k]) / motion[k];
+        let t2 = (cb[k] + grown[k] - start[k]) / motion[k];
+        let (t1, t2) = (t1.min(t2), t1.max(t2));
+        if t1 > t_near {
+            t_near = t1;
+            near_axis = k;
+        }
+        t_far = t_far.min(t2);
+    }
+    if t_near > t_far || t_near < 0.0 || t_near > 1.0 {
+        return None;
+    }
+    let q = vec2_add(start, vec2_scale(motion, t_near));
+    let rel = vec2_sub(q, cb);
+    let sign = |v: f64| if v < 0.0 { -1.0 } else { 1.0 };
+    if rel[0].abs() <= half[0] || rel[1].abs() <= half[1] {
+        let mut normal = [0.0, 0.0];
+        normal[near_axis] = sign(rel[near_axis]);
+        return Some((t_near, normal));
+    }
+    // ...except that its corners are rounded, so there test the corner itself.
+    let corner = [cb[0] + sign(rel[0]) * half[0], cb[1] + sign(rel[1]) * half[1]];
+    let m = vec2_sub(start, corner);
+    let a = vec2_square_len(motion);
+    let b = vec2_dot(m, motion);
+    let c = vec2_square_len(m) - r * r;
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let t = (-b - disc.sqrt()) / a;
+    if t < 0.0 || t > 1.0 {
+        return None;
+    }
+    let at = vec2_add(start, vec2_scale(motion, t));
+    Some((t, vec2_normalized(vec2_sub(at, corner))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector2<f64>, b: Vector2<f64>) -> bool {
+        (a[0] - b[0]).abs() < 1e-9 && (a[1] - b[1]).abs() < 1e-9
+    }
+
+    #[test]
+    fn circles() {
+        let c = circle_circle([0.0, 0.0], 1.0, [3.0, 0.0], 2.5).unwrap();
+        assert!(close(c.normal, [1.0, 0.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+        assert!(close(c.point, [1.0, 0.0]));
+        assert_eq!(circle_circle([0.0, 0.0], 1.0, [3.0, 0.0], 2.0), None);
+    }
+
+    #[test]
+    fn circle_against_box_face() {
+        let c = aabb_circle([0.0, 0.0], [2.0, 1.0], [0.5, 1.5], 1.0).unwrap();
+        assert!(close(c.normal, [0.0, 1.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+        assert!(close(c.point, [0.5, 1.0]));
+        assert_eq!(aabb_circle([0.0, 0.0], [2.0, 1.0], [0.0, 2.0], 1.0), None);
+    }
+
+    #[test]
+    fn circle_inside_box_leaves_by_the_nearest_face() {
+        let c = aabb_circle([0.0, 0.0], [2.0, 1.0], [1.5, 0.0], 0.5).unwrap();
+        assert!(close(c.normal, [1.0, 0.0]));
+        assert!((c.depth - 1.0).abs() < 1e-9);
+        assert!(close(c.point, [2.0, 0.0]));
+    }
+
+    #[test]
+    fn boxes_separate_along_the_shallower_axis() {
+        let c = aabb_aabb([0.0, 0.0], [1.0, 1.0], [-1.5, 0.2], [1.0, 1.0]).unwrap();
+        assert!(close(c.normal, [-1.0, 0.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+        assert_eq!(aabb_aabb([0.0, 0.0], [1.0, 1.0], [2.0, 0.0], [1.0, 1.0]), None);
+    }
+
+    #[test]
+    fn segments() {
+        let c = segment_circle([0.0, 0.0, 10.0, 0.0], [5.0, 0.5], 1.0).unwrap();
+        assert!(close(c.normal, [0.0, 1.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+        assert!(close(c.point, [5.0, 0.0]));
+
+        let c = segment_aabb([0.0, 0.0, 10.0, 0.0], [5.0, -0.5], [1.0, 1.0]).unwrap();
+        assert!(close(c.normal, [0.0, -1.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normal_points_from_first_to_second() {
+        let ball = Position { x: 0.0, y: 1.5 };
+        let paddle = Position { x: 0.0, y: 0.0 };
+        let (circle, square) = (ShapeVariant::Circle(1.0), ShapeVariant::Square(2.0, 1.0));
+        let c = contact(&paddle, &square, &ball, &circle).unwrap();
+        assert!(close(c.normal, [0.0, 1.0]));
+        let c = contact(&ball, &circle, &paddle, &square).unwrap();
+        assert!(close(c.normal, [0.0, -1.0]));
+        assert!((c.depth - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sweep_hits_a_face() {
+        let (t, n) = sweep_circle_aabb([-10.0, 0.0], [20.0, 0.0], 1.0, [0.0, 0.0], [1.0, 1.0]).unwrap();
+        assert!((t - 0.4).abs() < 1e-9);
+        assert!(close(n, [-1.0, 0.0]));
+    }
+
+    #[test]
+    fn sweep_hits_a_rounded_corner() {
+        let (t, n) = sweep_circle_aabb([-3.0, -3.0], [2.0, 2.0], 1.0, [0.0, 0.0], [1.0, 1.0]).unwrap();
+        let half = 0.5f64.sqrt();
+        assert!((t - (1.0 - half / 2.0)).abs() < 1e-9);
+        assert!(close(n, [-half, -half]));
+    }
+
+    #[test]
+    fn sweep_misses() {
+        let cb = [0.0, 0.0];
+        let half = [1.0, 1.0];
+        // Passes beside the box.
+        assert_eq!(sweep_circle_aabb([-10.0, 5.0], [20.0, 0.0], 1.0, cb, half), None);
+        // Would hit, but not within this motion.
+        assert_eq!(sweep_circle_aabb([-10.0, 0.0], [5.0, 0.0], 1.0, cb, half), None);
+        // Cuts the grown box's corner without touching the rounded one.
+        assert_eq!(sweep_circle_aabb([-3.8, 0.2], [4.0, -4.0], 1.0, cb, half), None);
+        // Already overlapping is left to `contact`.
+        assert_eq!(sweep_circle_aabb([0.5, 0.0], [20.0, 0.0], 1.0, cb, half), None);
+    }
+}
@@ -1,11 +1,30 @@
 use std::default::Default;
 use piston::input::keyboard;
+use piston::input::mouse::MouseButton;
+use ecs::keys;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum ClampVariant {
     Bounce,
     Stop,
-    Remove // Acts when item leaves window.
+    Remove, // Acts when item leaves window.
+    Goal // Bounces off top and bottom, scores when leaving left or right.
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom
+}
+
+/// Published by MoveSystem into `Services::exits` when it removes an entity
+/// for leaving the view.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExitEvent {
+    pub entity: ::ecsrs::Entity,
+    pub edge: Edge
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -13,10 +32,135 @@ pub struct WindowClamp {
     pub variant: ClampVariant
 }
 
+/// Something held down to move a paddle. Written `W`, `mouse:Left` or
+/// `pad0:button3` in data files.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+    Key(keyboard::Key),
+    Mouse(MouseButton),
+    Pad { id: i32, button: u8 }
+}
+
+// `pad0:button3` or `pad0:axis1` as (0, "button", 3).
+fn pad_part(s: &str) -> Option<(i32, &str, u8)> {
+    if !s.starts_with("pad") {
+        return None;
+    }
+    let mut parts = s[3..].splitn(2, ':');
+    let id = match parts.next().and_then(|id| id.parse().ok()) { Some(id) => id, None => return None };
+    let rest = match parts.next() { Some(rest) => rest, None => return None };
+    let digits = rest.find(|c: char| c.is_digit(10)).unwrap_or(rest.len());
+    rest[digits..].parse().ok().map(|n| (id, &rest[..digits], n))
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match *self {
+            Binding::Key(key) => keys::key_name(key),
+            Binding::Mouse(button) => format!("mouse:{}", keys::mouse_button_name(button)),
+            Binding::Pad { id, button } => format!("pad{}:button{}", id, button)
+        }
+    }
+}
+
+impl ::std::str::FromStr for Binding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Binding, String> {
+        if s.starts_with("mouse:") {
+            return keys::mouse_button_from_name(&s[6..]).map(Binding::Mouse);
+        }
+        match pad_part(s) {
+            Some((id, "button", button)) => Ok(Binding::Pad { id: id, button: button }),
+            Some(_) => Err(format!("Unknown binding '{}', expected pad<id>:button<n>", s)),
+            None => keys::key_from_name(s).map(Binding::Key)
+        }
+    }
+}
+
+/// Something that moves a paddle by how far it is pushed rather than by being
+/// held. Written `mouse` or `pad0:axis1`, with a leading `-` to flip it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Axis {
+    MouseY, // The paddle chases the cursor.
+    Pad { id: i32, axis: u8, invert: bool } // Speed in proportion to the stick.
+}
+
+impl Axis {
+    pub fn name(&self) -> String {
+        match *self {
+            Axis::MouseY => "mouse".to_string(),
+            Axis::Pad { id, axis, invert } => format!("{}pad{}:axis{}", if invert { "-" } else { "" }, id, axis)
+        }
+    }
+}
+
+impl ::std::str::FromStr for Axis {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Axis, String> {
+        let invert = s.starts_with('-');
+        let name = if invert { &s[1..] } else { s };
+        match (name, pad_part(name)) {
+            ("mouse", _) if !invert => Ok(Axis::MouseY),
+            (_, Some((id, "axis", axis))) => Ok(Axis::Pad { id: id, axis: axis, invert: invert }),
+            _ => Err(format!("Unknown axis '{}', expected mouse or [-]pad<id>:axis<n>", s))
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct PlayerController {
-    pub up: keyboard::Key,
-    pub down: keyboard::Key
+    pub up: Vec<Binding>, // Any of them will do.
+    pub down: Vec<Binding>,
+    pub axis: Option<Axis>,
+    pub accel: Option<f64>, // px/s² towards full speed, None to get there at once.
+    pub decel: Option<f64> // px/s² when slowing or turning round.
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard
+}
+
+impl ::std::str::FromStr for Difficulty {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("Unknown difficulty '{}', expected easy, medium or hard", s))
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AiController {
+    pub reaction: f64, // Seconds between looks at the ball.
+    pub max_speed: f64,
+    pub error: f64, // Largest random aim offset in pixels.
+    pub predict: bool, // Follow the ball's path through wall bounces instead of its current y.
+    pub target: Option<f64>,
+    pub cooldown: f64
+}
+
+impl AiController {
+    pub fn new(difficulty: Difficulty) -> AiController {
+        let (reaction, max_speed, error, predict) = match difficulty {
+            Difficulty::Easy => (0.4, 300.0, 40.0, false),
+            Difficulty::Medium => (0.2, 500.0, 20.0, true),
+            Difficulty::Hard => (0.05, 800.0, 5.0, true)
+        };
+        AiController {
+            reaction: reaction,
+            max_speed: max_speed,
+            error: error,
+            predict: predict,
+            target: None,
+            cooldown: 0.0
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -49,6 +193,43 @@ pub struct Shape {
     pub border: Option<f64>
 }
 
+pub mod layers {
+    pub const GAMEPLAY: u32 = 1 << 0;
+    pub const EFFECTS: u32 = 1 << 1;
+    pub const DEBUG: u32 = 1 << 2;
+    pub const NONE: u32 = 0;
+    pub const ALL: u32 = !0;
+}
+
+/// Only entities carrying one of these are collided at all. Two of them
+/// collide when each one's category is in the other's mask.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CollisionLayer {
+    pub category: u32,
+    pub mask: u32
+}
+
+impl CollisionLayer {
+    pub fn gameplay() -> CollisionLayer {
+        CollisionLayer { category: layers::GAMEPLAY, mask: layers::GAMEPLAY }
+    }
+
+    pub fn effects() -> CollisionLayer {
+        CollisionLayer { category: layers::EFFECTS, mask: layers::NONE }
+    }
+
+    pub fn debug() -> CollisionLayer {
+        CollisionLayer { category: layers::DEBUG, mask: layers::NONE }
+    }
+
+    pub fn collides(&self, other: &CollisionLayer) -> bool {
+        self.category & other.mask != 0 && other.category & self.mask != 0
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Mass(pub f64); // Entities without one collide as if they had Mass(1.0).
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Velocity {
     pub x: f64,
@@ -57,8 +238,194 @@ pub struct Velocity {
 
 pub type Color = ::graphics::types::Color;
 
+/// A label drawn over everything else, its baseline starting `offset` from
+/// the entity's position.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Text {
+    pub text: String,
+    pub size: u32,
+    pub color: Color,
+    pub offset: [f64; 2]
+}
+
+/// Marks the text HudSystem keeps up to date.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hud {
+    Score,
+    Rally,
+    Fps,
+    Message // Who won, and the like.
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct HitCount {
     pub recent: bool,
     pub count: u32
 }
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Side {
+    Left,
+    Right
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WinCondition {
+    pub first_to: u32,
+    pub win_by: u32
+}
+
+impl WinCondition {
+    pub fn new(first_to: u32, win_by: u32) -> Result<WinCondition, String> {
+        if first_to == 0 || win_by == 0 {
+            return Err("first_to and win_by should both be at least 1".to_string());
+        }
+        Ok(WinCondition { first_to: first_to, win_by: win_by })
+    }
+}
+
+impl Default for WinCondition {
+    fn default() -> WinCondition { WinCondition { first_to: 11, win_by: 2 } }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Score {
+    pub left: u32,
+    pub right: u32,
+    pub win: WinCondition,
+    pub goals: Vec<Side>, // Side that scored, waiting to be awarded.
+    pub winner: Option<Side>
+}
+
+impl Score {
+    pub fn new(win: WinCondition) -> Score {
+        Score {
+            left: 0,
+            right: 0,
+            win: win,
+            goals: Vec::new(),
+            winner: None
+        }
+    }
+
+    pub fn award(&mut self, side: Side) {
+        if self.winner.is_some() {
+            return;
+        }
+        match side {
+            Side::Left => self.left += 1,
+            Side::Right => self.right += 1
+        }
+        let (leader, lead, points) = if self.left >= self.right {
+            (Side::Left, self.left - self.right, self.left)
+        } else {
+            (Side::Right, self.right - self.left, self.right)
+        };
+        if points >= self.win.first_to && lead >= self.win.win_by {
+            self.winner = Some(leader);
+        }
+    }
+}
+
+impl Default for Score {
+    fn default() -> Score { Score::new(WinCondition::default()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(win: WinCondition, points: &[Side]) -> Score {
+        let mut score = Score::new(win);
+        for side in points {
+            score.award(*side);
+        }
+        score
+    }
+
+    #[test]
+    fn first_to_threshold() {
+        let win = WinCondition { first_to: 3, win_by: 1 };
+        let score = play(win.clone(), &[Side::Left, Side::Right, Side::Left]);
+        assert_eq!(score.winner, None);
+        let score = play(win, &[Side::Left, Side::Right, Side::Left, Side::Left]);
+        assert_eq!(score.winner, Some(Side::Left));
+        assert_eq!((score.left, score.right), (3, 1));
+    }
+
+    #[test]
+    fn deuce_needs_a_two_point_lead() {
+        let mut points = Vec::new();
+        for _ in 0..10 {
+            points.push(Side::Left);
+            points.push(Side::Right);
+        }
+        points.push(Side::Left); // 11 - 10
+        let mut score = play(WinCondition::default(), &points);
+        assert_eq!(score.winner, None);
+        score.award(Side::Right); // 11 - 11
+        score.award(Side::Right); // 11 - 12
+        assert_eq!(score.winner, None);
+        score.award(Side::Right); // 11 - 13
+        assert_eq!(score.winner, Some(Side::Right));
+    }
+
+    #[test]
+    fn pad_parts() {
+        assert_eq!(pad_part("pad0:button3"), Some((0, "button", 3)));
+        assert_eq!(pad_part("pad12:axis1"), Some((12, "axis", 1)));
+        assert_eq!(pad_part("pad0:axis"), None);
+        assert_eq!(pad_part("padx:button3"), None);
+        assert_eq!(pad_part("pad0"), None);
+        assert_eq!(pad_part("W"), None);
+    }
+
+    #[test]
+    fn bindings_from_names() {
+        assert_eq!("W".parse(), Ok(Binding::Key(keyboard::Key::W)));
+        assert_eq!("mouse:Left".parse(), Ok(Binding::Mouse(MouseButton::Left)));
+        assert_eq!("pad1:button9".parse(), Ok(Binding::Pad { id: 1, button: 9 }));
+        // An axis can't be held down like a button.
+        assert!("pad0:axis1".parse::<Binding>().unwrap_err().contains("expected pad<id>:button<n>"));
+        assert!("mouse:Nose".parse::<Binding>().is_err());
+        assert!("Nope".parse::<Binding>().is_err());
+        for name in &["W", "mouse:Left", "pad1:button9"] {
+            assert_eq!(name.parse::<Binding>().unwrap().name(), *name);
+        }
+    }
+
+    #[test]
+    fn axes_from_names() {
+        assert_eq!("mouse".parse(), Ok(Axis::MouseY));
+        assert_eq!("pad0:axis1".parse(), Ok(Axis::Pad { id: 0, axis: 1, invert: false }));
+        assert_eq!("-pad0:axis1".parse(), Ok(Axis::Pad { id: 0, axis: 1, invert: true }));
+        assert!("-mouse".parse::<Axis>().is_err());
+        assert!("pad0:button1".parse::<Axis>().is_err());
+        assert!("pad0:axis".parse::<Axis>().is_err());
+        for name in &["mouse", "pad0:axis1", "-pad0:axis1"] {
+            assert_eq!(name.parse::<Axis>().unwrap().name(), *name);
+        }
+    }
+
+    #[test]
+    fn win_condition_needs_at_least_one_point_and_lead() {
+        assert_eq!(WinCondition::new(3, 1), Ok(WinCondition { first_to: 3, win_by: 1 }));
+        assert!(WinCondition::new(0, 2).is_err());
+        assert!(WinCondition::new(11, 0).is_err());
+    }
+
+    #[test]
+    fn win_by_two_before_first_to() {
+        let score = play(WinCondition::default(), &[Side::Right; 10]);
+        assert_eq!(score.winner, None);
+        let score = play(WinCondition::default(), &[Side::Right; 11]);
+        assert_eq!(score.winner, Some(Side::Right));
+    }
+
+    #[test]
+    fn no_points_after_a_win() {
+        let mut score = play(WinCondition { first_to: 1, win_by: 1 }, &[Side::Left]);
+        score.award(Side::Right);
+        assert_eq!((score.left, score.right, score.winner), (1, 0, Some(Side::Left)));
+    }
+}
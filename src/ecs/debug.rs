@@ -0,0 +1,141 @@
+use piston::input::keyboard::Key;
+use ecs::broadphase::Bounds;
+use ecs::components::{Color, Position};
+use ecs::render::Draw;
+
+// Updates a mark left by a system stays on screen for, fading out.
+const MARK_TICKS: u32 = 60;
+// Velocity vectors show where things will be this many seconds from now.
+const VELOCITY_SECONDS: f64 = 0.1;
+const GREEN: Color = [0.0, 0.8, 0.0, 1.0];
+const YELLOW: Color = [0.9, 0.9, 0.0, 1.0];
+const CYAN: Color = [0.0, 0.8, 0.8, 1.0];
+
+/// Which parts of the overlay are shown. Everything is off unless asked for.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Toggles {
+    pub velocities: bool,
+    pub normals: bool, // At each collision.
+    pub ghosts: bool, // Where the ball was when a paddle hit it.
+    pub bounds: bool,
+    pub ids: bool
+}
+
+// `all`, or a comma separated list like `velocities,normals`.
+impl ::std::str::FromStr for Toggles {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Toggles, String> {
+        let mut t = Toggles::default();
+        for name in s.split(',').map(|n| n.trim()) {
+            match name {
+                "all" => t = Toggles { velocities: true, normals: true, ghosts: true, bounds: true, ids: true },
+                "velocities" => t.velocities = true,
+                "normals" => t.normals = true,
+                "ghosts" => t.ghosts = true,
+                "bounds" => t.bounds = true,
+                "ids" => t.ids = true,
+                other => return Err(format!("Unknown debug overlay '{}', expected velocities, normals, ghosts, bounds, ids or all", other))
+            }
+        }
+        Ok(t)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Mark {
+    draw: Draw,
+    left: u32 // Updates until it's gone.
+}
+
+/// Immediate mode debug drawing. Systems say what they'd like seen as they
+/// run and DrawSystem puts it over the next frame, so nothing is spawned into
+/// the world. Each call does nothing unless its part is shown.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DebugOverlay {
+    pub show: Toggles,
+    marks: Vec<Mark>, // Left by updates, kept for a while.
+    frame: Vec<Draw> // Only for the frame being drawn.
+}
+
+impl DebugOverlay {
+    /// F1 velocities, F2 normals, F3 ghosts, F4 bounds and F10 ids. Returns
+    /// whether `key` was one of them.
+    pub fn key(&mut self, key: Key) -> bool {
+        let show = &mut self.show;
+        let toggle = match key {
+            Key::F1 => &mut show.velocities,
+            Key::F2 => &mut show.normals,
+            Key::F3 => &mut show.ghosts,
+            Key::F4 => &mut show.bounds,
+            Key::F10 => &mut show.ids,
+            _ => return false
+        };
+        *toggle = !*toggle;
+        true
+    }
+
+    /// Ages the marks, call once per fixed update.
+    pub fn tick(&mut self) {
+        for mark in self.marks.iter_mut() {
+            mark.left -= 1;
+        }
+        self.marks.retain(|m| m.left > 0);
+    }
+
+    pub fn normal(&mut self, point: [f64; 2], normal: [f64; 2]) {
+        if self.show.normals {
+            let tip = [point[0] + normal[0] * 20.0, point[1] + normal[1] * 20.0];
+            self.marks.push(Mark { draw: Draw::Line(YELLOW, [point[0], point[1], tip[0], tip[1]], 1.0), left: MARK_TICKS });
+        }
+    }
+
+    /// `draw` is the entity as it looks now.
+    pub fn ghost(&mut self, draw: Draw) {
+        if self.show.ghosts {
+            self.marks.push(Mark { draw: draw, left: MARK_TICKS });
+        }
+    }
+
+    pub fn velocity(&mut self, p: &Position, v: [f64; 2]) {
+        if self.show.velocities {
+            let to = [p.x + v[0] * VELOCITY_SECONDS, p.y + v[1] * VELOCITY_SECONDS];
+            self.frame.push(Draw::Line(GREEN, [p.x, p.y, to[0], to[1]], 1.0));
+        }
+    }
+
+    pub fn bounds(&mut self, b: &Bounds) {
+        if self.show.bounds {
+            let (x1, y1, x2, y2) = (b.min[0], b.min[1], b.max[0], b.max[1]);
+            for l in [[x1, y1, x2, y1], [x2, y1, x2, y2], [x2, y2, x1, y2], [x1, y2, x1, y1]].iter() {
+                self.frame.push(Draw::Line(CYAN, *l, 0.5));
+            }
+        }
+    }
+
+    pub fn id(&mut self, p: &Position, id: u64) {
+        if self.show.ids {
+            self.frame.push(Draw::Text(CYAN, 10, [p.x + 4.0, p.y - 4.0], format!("{}", id)));
+        }
+    }
+
+    /// Everything to draw over this frame, marks fading as they age. Clears
+    /// what was only for this frame.
+    pub fn drain(&mut self) -> Vec<Draw> {
+        let mut out: Vec<Draw> = self.marks.iter().map(|m| {
+            faded(&m.draw, m.left as f32 / MARK_TICKS as f32)
+        }).collect();
+        out.extend(self.frame.drain(..));
+        out
+    }
+}
+
+fn faded(draw: &Draw, alpha: f32) -> Draw {
+    let fade = |c: Color| [c[0], c[1], c[2], c[3] * alpha];
+    match *draw {
+        Draw::Clear(c) => Draw::Clear(fade(c)),
+        Draw::Rectangle(c, r) => Draw::Rectangle(fade(c), r),
+        Draw::Ellipse(c, r) => Draw::Ellipse(fade(c), r),
+        Draw::Line(c, l, radius) => Draw::Line(fade(c), l, radius),
+        Draw::Text(c, size, at, ref text) => Draw::Text(fade(c), size, at, text.clone())
+    }
+}
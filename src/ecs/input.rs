@@ -0,0 +1,140 @@
+use piston::input::{Button, Event, Input, Key, Motion};
+use ecs::components::{Axis, Binding, PlayerController};
+
+/// One paddle's controls for one update, boiled down from whatever is bound
+/// to it. Small enough to send over the network every tick.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PaddleInput {
+    pub up: bool,
+    pub down: bool,
+    pub push: f64, // Stick position from -1 to 1.
+    pub mouse_y: Option<f64>, // In view coordinates, when following the mouse.
+    pub restart: bool // Space or Return, to play again once a network game is over.
+}
+
+impl PaddleInput {
+    pub fn sample(input: &InputState, controller: &PlayerController, padding: f64) -> PaddleInput {
+        PaddleInput {
+            up: input.any_held(&controller.up),
+            down: input.any_held(&controller.down),
+            push: controller.axis.and_then(|a| input.push(a)).unwrap_or(0.0),
+            mouse_y: match (controller.axis, input.mouse) {
+                (Some(Axis::MouseY), Some(mouse)) => Some(mouse[1] - padding),
+                _ => None
+            },
+            restart: input.is_held(Binding::Key(Key::Space)) || input.is_held(Binding::Key(Key::Return))
+        }
+    }
+}
+
+/// What is held down, and where the mouse and sticks are, as of the latest
+/// event. Kept up to date by InputSystem so other systems can ask about the
+/// state of things on any update instead of catching the events themselves.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct InputState {
+    pub held: Vec<Button>,
+    pub mouse: Option<[f64; 2]>, // Window coordinates, once it has moved.
+    pub axes: Vec<((i32, u8), f64)> // Latest position of each (pad, axis) seen.
+}
+
+impl InputState {
+    pub fn event(&mut self, event: &Event) {
+        match *event {
+            Event::Input(Input::Press(button)) => {
+                if !self.held.contains(&button) {
+                    self.held.push(button);
+                }
+            }
+            Event::Input(Input::Release(button)) => self.held.retain(|b| *b != button),
+            Event::Input(Input::Move(Motion::MouseCursor(x, y))) => self.mouse = Some([x, y]),
+            Event::Input(Input::Move(Motion::ControllerAxis(ref args))) => {
+                let key = (args.id, args.axis);
+                match self.axes.iter().position(|&(k, _)| k == key) {
+                    Some(i) => self.axes[i].1 = args.position,
+                    None => self.axes.push((key, args.position))
+                }
+            }
+            // Keys let go of while the window was away never send a release.
+            Event::Input(Input::Focus(false)) => self.held.clear(),
+            _ => ()
+        }
+    }
+
+    pub fn is_held(&self, binding: Binding) -> bool {
+        self.held.iter().any(|button| match (binding, *button) {
+            (Binding::Key(k), Button::Keyboard(key)) => k == key,
+            (Binding::Mouse(m), Button::Mouse(mouse)) => m == mouse,
+            (Binding::Pad { id, button }, Button::Controller(pad)) => id == pad.id && button == pad.button,
+            _ => false
+        })
+    }
+
+    pub fn any_held(&self, bindings: &[Binding]) -> bool {
+        bindings.iter().any(|b| self.is_held(*b))
+    }
+
+    /// A stick's position from -1 to 1, flipped if the axis asks for it.
+    /// None for the mouse, whose position isn't a push in either direction.
+    pub fn push(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::MouseY => None,
+            Axis::Pad { id, axis, invert } => self.axes.iter()
+                .find(|&&(k, _)| k == (id, axis))
+                .map(|&(_, p)| if invert { -p } else { p })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::{ControllerAxisArgs, ControllerButton, Key};
+    use piston::input::mouse::MouseButton;
+
+    fn controller(axis: Option<Axis>) -> PlayerController {
+        PlayerController {
+            up: vec![Binding::Key(Key::W), Binding::Pad { id: 0, button: 9 }],
+            down: vec![Binding::Mouse(MouseButton::Left)],
+            axis: axis,
+            accel: None,
+            decel: None
+        }
+    }
+
+    fn feed(input: &mut InputState, event: Input) {
+        input.event(&Event::Input(event));
+    }
+
+    #[test]
+    fn held_until_released() {
+        let controller = controller(None);
+        let mut input = InputState::default();
+        feed(&mut input, Input::Press(Button::Keyboard(Key::W)));
+        feed(&mut input, Input::Press(Button::Controller(ControllerButton { id: 0, button: 9 })));
+        feed(&mut input, Input::Press(Button::Keyboard(Key::W)));
+        assert_eq!(input.held.len(), 2);
+        feed(&mut input, Input::Release(Button::Keyboard(Key::W)));
+        // The pad button still holds it up.
+        assert_eq!(PaddleInput::sample(&input, &controller, 0.0), PaddleInput { up: true, ..PaddleInput::default() });
+        feed(&mut input, Input::Press(Button::Mouse(MouseButton::Left)));
+        let both = PaddleInput::sample(&input, &controller, 0.0);
+        assert!(both.up && both.down);
+        feed(&mut input, Input::Focus(false));
+        assert_eq!(PaddleInput::sample(&input, &controller, 0.0), PaddleInput::default());
+    }
+
+    #[test]
+    fn sticks_and_mouse() {
+        let mut input = InputState::default();
+        feed(&mut input, Input::Move(Motion::ControllerAxis(ControllerAxisArgs { id: 0, axis: 1, position: 0.5 })));
+        feed(&mut input, Input::Move(Motion::ControllerAxis(ControllerAxisArgs { id: 0, axis: 1, position: 0.25 })));
+        feed(&mut input, Input::Move(Motion::MouseCursor(10.0, 140.0)));
+        assert_eq!(input.axes.len(), 1);
+        let flipped = controller(Some(Axis::Pad { id: 0, axis: 1, invert: true }));
+        assert_eq!(PaddleInput::sample(&input, &flipped, 40.0).push, -0.25);
+        let other = controller(Some(Axis::Pad { id: 1, axis: 1, invert: false }));
+        assert_eq!(PaddleInput::sample(&input, &other, 40.0).push, 0.0);
+        let mouse = PaddleInput::sample(&input, &controller(Some(Axis::MouseY)), 40.0);
+        assert_eq!((mouse.push, mouse.mouse_y), (0.0, Some(100.0)));
+    }
+}
@@ -0,0 +1,48 @@
+use piston::input::keyboard::Key;
+use piston::input::mouse::MouseButton;
+
+// Keys that can be named in data files, by their variant name.
+const NAMED: &'static [Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::D0, Key::D1, Key::D2, Key::D3, Key::D4,
+    Key::D5, Key::D6, Key::D7, Key::D8, Key::D9,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4,
+    Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+    Key::Up, Key::Down, Key::Left, Key::Right,
+    Key::Space, Key::Return, Key::Tab, Key::Backspace, Key::Escape,
+    Key::LShift, Key::RShift, Key::LCtrl, Key::RCtrl, Key::LAlt, Key::RAlt,
+    Key::Comma, Key::Period, Key::Slash, Key::Semicolon, Key::Quote,
+    Key::LeftBracket, Key::RightBracket, Key::Minus, Key::Equals,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6,
+    Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::PageUp, Key::PageDown, Key::Home, Key::End,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Result<Key, String> {
+    NAMED.iter()
+        .find(|k| key_name(**k) == name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown key '{}'", name))
+}
+
+const NAMED_MOUSE: &'static [MouseButton] = &[
+    MouseButton::Left, MouseButton::Right, MouseButton::Middle,
+    MouseButton::X1, MouseButton::X2,
+];
+
+pub fn mouse_button_name(button: MouseButton) -> String {
+    format!("{:?}", button)
+}
+
+pub fn mouse_button_from_name(name: &str) -> Result<MouseButton, String> {
+    NAMED_MOUSE.iter()
+        .find(|b| mouse_button_name(**b) == name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown mouse button '{}'", name))
+}
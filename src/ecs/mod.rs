@@ -1,95 +1,94 @@
 pub mod systems;
 pub mod components;
-
-use self::components::*;
-use std::cell::RefCell;
-use std::cell::Cell;
-use std::rc::Rc;
-
-pub mod debug {
-    use ecsrs::*;
-    use ecs::scaffold::*;
-    use ecs::components::*;
-    use graphics::{self};
-
-    pub fn ghost(d: &mut DataHelper<Components, Services>, entity: &EntityData<Components>) {
-        let ghost = entity.clone();
-        d.create_entity(ghost);
-    }
-
-    pub fn line(d: &mut DataHelper<Components, Services>, line: [f64; 4], speed: f32) {
-        d.create_entity(|entity: BuildData<Components>, data: &mut Components| {
-            data.positions.add(&entity,
-                Position{
-                    x: (line[0] + line[2]) / 2.0,
-                    y: (line[1] + line[3]) / 2.0
-                }
-            );
-            data.shapes.add(&entity,
-                Shape {
-                    variant: ShapeVariant::Line(line),
-                    border: None
-                }
-            );
-            data.colors.add(&entity, [0.0, 0.8, 0.0, 1.0]);
-            data.fades.add(&entity, Fade(speed));
-        });
-    }
-}
+pub mod arena;
+pub mod input;
+pub mod keys;
+pub mod bindings;
+pub mod prefab;
+pub mod raster;
+pub mod render;
+pub mod rng;
+pub mod snapshot;
+pub mod state;
+pub mod broadphase;
+pub mod collide;
+pub mod debug;
 
 pub mod scaffold {
     use ecs;
-    use piston::input::{Event, ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
-    use ecsrs::system::{EntityProcess, EntitySystem};
+    use piston::input::{Event, UpdateArgs};
+    use ecsrs::system::EntitySystem;
     use ecsrs::*;
     use super::components::*;
+    use rand::{self, Rng};
+    use ecs::rng::SimRng;
     use std::cell::RefCell;
-    use std::cell::Cell;
-    use std::rc::Rc;
 
     components! {
         struct Components {
+            #[hot] ai_controllers: AiController,
             #[hot] clamps: WindowClamp,
+            #[hot] collision_layers: CollisionLayer,
             #[hot] colors: Color,
             #[hot] fades: Fade,
             #[hot] hit_counts: HitCount,
+            #[hot] huds: Hud,
+            #[hot] masses: Mass,
             #[hot] player_controllers: PlayerController,
             #[hot] positions: Position,
+            #[hot] previous_positions: Position, // Where the last fixed step started, for drawing in between.
             #[hot] shapes: Shape,
             #[hot] shimmers: Shimmer,
+            #[hot] texts: Text,
             #[hot] velocities: Velocity,
         }
     }
 
     systems! {
         struct Systems<Components, Services> {
+            input: ecs::systems::InputSystem = ecs::systems::InputSystem,
+            state: ecs::systems::StateSystem = ecs::systems::StateSystem,
+            debug: ecs::systems::DebugSystem = ecs::systems::DebugSystem,
             fade: EntitySystem<ecs::systems::FadeSystem> = EntitySystem::new(
                 ecs::systems::FadeSystem,
                 aspect!(<Components> all: [colors, fades])
             ),
             control: EntitySystem<ecs::systems::ControlSystem> = EntitySystem::new(
                 ecs::systems::ControlSystem,
-                aspect!(<Components> all: [player_controllers, velocities])
+                aspect!(<Components> all: [player_controllers, positions, velocities])
             ),
-            collisions: EntitySystem<ecs::systems::CollisionSystem> = EntitySystem::new(
-                ecs::systems::CollisionSystem,
+            ai: EntitySystem<ecs::systems::AiSystem> = EntitySystem::new(
+                ecs::systems::AiSystem,
                 aspect!(<Components> all: [positions, shapes, velocities])
             ),
+            collisions: EntitySystem<ecs::systems::CollisionSystem> = EntitySystem::new(
+                ecs::systems::CollisionSystem::new(),
+                aspect!(<Components> all: [positions, shapes, velocities, collision_layers])
+            ),
             moves: EntitySystem<ecs::systems::MoveSystem> = EntitySystem::new(
                 ecs::systems::MoveSystem,
                 aspect!(<Components> all: [positions, shapes, velocities, clamps])
             ),
+            score: ecs::systems::ScoreSystem = ecs::systems::ScoreSystem,
             shimmer: EntitySystem<ecs::systems::ShimmerSystem> = EntitySystem::new(
                 ecs::systems::ShimmerSystem,
                 aspect!(<Components> all: [colors, shimmers])
             ),
-            draw: EntitySystem<ecs::systems::DrawSystem> = EntitySystem::new(
-                ecs::systems::DrawSystem{ gl: None },
-                aspect!(<Components> all: [positions, shapes, colors])
+            snapshot: EntitySystem<ecs::snapshot::SnapshotSystem> = EntitySystem::new(
+                ecs::snapshot::SnapshotSystem::new(),
+                aspect!(<Components> all: [positions] none: [huds])
+            ),
+            hud: EntitySystem<ecs::systems::HudSystem> = EntitySystem::new(
+                ecs::systems::HudSystem::new(),
+                aspect!(<Components> all: [huds, texts])
+            ),
+            draw: EntitySystem<ecs::systems::DrawSystem<ecs::render::Target>> = EntitySystem::new(
+                ecs::systems::DrawSystem{ target: None },
+                aspect!(<Components> all: [positions])
             ),
             game: EntitySystem<ecs::systems::GameSystem> = EntitySystem::new(
-                ecs::systems::GameSystem,
-                aspect!(<Components> all: [hit_counts])
+                ecs::systems::GameSystem::new(),
+                aspect!(<Components> all: [positions, shapes, collision_layers])
             )
         }
     }
@@ -97,33 +96,62 @@ pub mod scaffold {
 
     pub struct Services {
         pub event: RefCell<Event>,
+        pub score: Score,
+        pub seed: u64,
+        pub rng: SimRng,
+        pub alpha: f64, // Fraction of a fixed step elapsed since the last update.
+        pub collisions: Vec<ecs::collide::CollisionEvent>, // Cleared at the start of every update.
+        pub exits: Vec<ExitEvent>, // Likewise.
+        pub arena: ecs::arena::Arena,
+        pub prefabs: ecs::prefab::Prefabs,
+        pub input: ecs::input::InputState,
+        pub net_input: Option<[ecs::input::PaddleInput; 2]>, // Left and right paddles, overriding `input` in a network game.
+        pub latency: Option<f64>, // Round trip to the other player, in seconds.
+        pub rally: u32, // Paddle hits since the last serve.
+        pub state: ecs::state::GameState,
+        pub time: ::timestep::TimeControl, // How fast the game is run, for debugging.
+        pub debug: ecs::debug::DebugOverlay,
     }
 
     impl ServiceManager for Services {}
 
-    impl Default for Services {
-        fn default() -> Services {
-            Services {
-                event: RefCell::new(Event::Update(UpdateArgs { dt: 3.14 }))
-            }
+    /// Entities in the order they were created. Systems keep theirs in a hash
+    /// map, so anything that draws from the rng or resolves one thing before
+    /// another should go through here to play out the same every run.
+    pub fn in_order<'a>(entities: EntityIter<'a, Components>) -> Vec<EntityData<'a, Components>> {
+        let mut evec: Vec<EntityData<Components>> = entities.collect();
+        evec.sort_by_key(|e| e.id());
+        evec
+    }
+
+    impl Services {
+        pub fn reseed(&mut self, seed: u64) {
+            self.seed = seed;
+            self.rng = SimRng::from_seed(seed);
         }
     }
 
-    impl<'a> EntityBuilder<Components> for EntityData<'a, Components> {
-        fn build<'b>(self, b: BuildData<'b, Components>, t: &mut Components) {
-            if t.colors.has(&self) {
-                let color = t.colors[self];
-                t.colors.add(&b, color);
-            }
-            if t.shapes.has(&self) {
-                let shape = t.shapes[self].clone();
-                t.shapes.add(&b, shape);
-            }
-            if t.positions.has(&self) {
-                let pos = t.positions[self].clone();
-                t.positions.add(&b, pos);
+    impl Default for Services {
+        fn default() -> Services {
+            let seed = rand::thread_rng().gen();
+            Services {
+                event: RefCell::new(Event::Update(UpdateArgs { dt: 3.14 })),
+                score: Score::default(),
+                seed: seed,
+                rng: SimRng::from_seed(seed),
+                alpha: 0.0,
+                collisions: Vec::new(),
+                exits: Vec::new(),
+                arena: ecs::arena::Arena::default(),
+                prefabs: ecs::prefab::Prefabs::default(),
+                input: ecs::input::InputState::default(),
+                net_input: None,
+                latency: None,
+                rally: 0,
+                state: ecs::state::GameState::default(),
+                time: ::timestep::TimeControl::default(),
+                debug: ecs::debug::DebugOverlay::default(),
             }
-            t.fades.add(&b, Fade(0.01));
         }
     }
 }
@@ -0,0 +1,596 @@
+use ecsrs::*;
+use rand::Rng;
+use rustc_serialize::json::{Json, Object};
+use std::path::Path;
+use ecs::components::*;
+use ecs::scaffold::{Components, Services};
+use files;
+
+/// A number in a prefab: either fixed, or drawn from `Services::rng` each
+/// time the prefab is spawned.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Fixed(f64),
+    Range { min: f64, max: f64, either_sign: bool }
+}
+
+impl Value {
+    pub fn draw<R: Rng>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Value::Fixed(v) => v,
+            Value::Range { min, max, either_sign } => {
+                let v = rng.gen_range(min, max);
+                if either_sign && rng.gen() { -v } else { v }
+            }
+        }
+    }
+}
+
+/// Point in the view that a prefab's position is an offset from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Anchor {
+    Origin, // Top left.
+    Center,
+    Left, // Middle of the left wall.
+    Right
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PositionSpec {
+    pub from: Anchor,
+    pub x: Value,
+    pub y: Value
+}
+
+/// Every component an entity can be built from, as read from a prefab file.
+/// Snapshots store each entity as one of these too, with every value fixed.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Prefab {
+    pub position: Option<PositionSpec>,
+    pub previous_position: Option<Position>,
+    pub velocity: Option<(Value, Value)>,
+    pub shape: Option<Shape>,
+    pub color: Option<Color>,
+    pub clamp: Option<WindowClamp>,
+    pub player_controller: Option<PlayerController>,
+    pub ai_controller: Option<AiController>,
+    pub shimmer: bool,
+    pub fade: Option<Fade>,
+    pub hit_count: Option<HitCount>,
+    pub mass: Option<Mass>,
+    pub collision_layer: Option<CollisionLayer>,
+    pub text: Option<Text>
+}
+
+fn number(j: &Json, what: &str) -> Result<f64, String> {
+    j.as_f64().ok_or_else(|| format!("{} should be a number, found {}", what, j))
+}
+
+fn string<'a>(j: &'a Json, what: &str) -> Result<&'a str, String> {
+    j.as_string().ok_or_else(|| format!("{} should be a string, found {}", what, j))
+}
+
+fn object<'a>(j: &'a Json, what: &str) -> Result<&'a Object, String> {
+    j.as_object().ok_or_else(|| format!("{} should be an object, found {}", what, j))
+}
+
+fn boolean(j: &Json, what: &str) -> Result<bool, String> {
+    j.as_boolean().ok_or_else(|| format!("{} should be true or false, found {}", what, j))
+}
+
+// A misspelt key would otherwise just leave its setting at the default.
+fn keys(o: &Object, allowed: &[&str], what: &str) -> Result<(), String> {
+    match o.keys().find(|k| !allowed.contains(&&k[..])) {
+        Some(k) if allowed.is_empty() => Err(format!("Unknown {} key '{}', it has no settings", what, k)),
+        Some(k) => Err(format!("Unknown {} key '{}', expected {}", what, k, allowed.join(", "))),
+        None => Ok(())
+    }
+}
+
+fn field<'a>(o: &'a Object, key: &str, what: &str) -> Result<&'a Json, String> {
+    o.get(key).ok_or_else(|| format!("{} is missing '{}'", what, key))
+}
+
+fn numbers(j: &Json, n: usize, what: &str) -> Result<Vec<f64>, String> {
+    let a = try!(j.as_array().ok_or_else(|| format!("{} should be a list of {} numbers, found {}", what, n, j)));
+    if a.len() != n {
+        return Err(format!("{} should be a list of {} numbers, found {}", what, n, j));
+    }
+    a.iter().map(|v| number(v, what)).collect()
+}
+
+// `5`, `[min, max]` or `{"min": .., "max": .., "either_sign": true}`.
+fn value(j: &Json, what: &str) -> Result<Value, String> {
+    let range = |min: f64, max: f64, either_sign: bool| {
+        if min < max {
+            Ok(Value::Range { min: min, max: max, either_sign: either_sign })
+        } else {
+            Err(format!("{} range needs min below max, found {} to {}", what, min, max))
+        }
+    };
+    match *j {
+        Json::Array(_) => {
+            let v = try!(numbers(j, 2, what));
+            range(v[0], v[1], false)
+        }
+        Json::Object(ref o) => {
+            try!(keys(o, &["min", "max", "either_sign"], what));
+            let min = try!(number(try!(field(o, "min", what)), what));
+            let max = try!(number(try!(field(o, "max", what)), what));
+            let either_sign = match o.get("either_sign") {
+                Some(b) => try!(boolean(b, &format!("{} either_sign", what))),
+                None => false
+            };
+            range(min, max, either_sign)
+        }
+        _ => number(j, what).map(Value::Fixed)
+    }
+}
+
+fn shape(j: &Json) -> Result<Shape, String> {
+    let what = "Shape";
+    if let Some(s) = j.as_string() {
+        return if s == "point" {
+            Ok(Shape { variant: ShapeVariant::Point, border: None })
+        } else {
+            Err(format!("Unknown Shape '{}', expected point or an object", s))
+        };
+    }
+    let o = try!(object(j, what));
+    try!(keys(o, &["circle", "square", "line", "border"], what));
+    let border = match o.get("border") {
+        Some(b) => Some(try!(number(b, "Shape border"))),
+        None => None
+    };
+    let variant = if let Some(r) = o.get("circle") {
+        ShapeVariant::Circle(try!(number(r, "Shape circle radius")))
+    } else if let Some(s) = o.get("square") {
+        let v = try!(numbers(s, 2, "Shape square half width and height"));
+        ShapeVariant::Square(v[0], v[1])
+    } else if let Some(l) = o.get("line") {
+        let v = try!(numbers(l, 4, "Shape line"));
+        ShapeVariant::Line([v[0], v[1], v[2], v[3]])
+    } else {
+        return Err(format!("Shape should have one of circle, square or line, found {}", j));
+    };
+    Ok(Shape { variant: variant, border: border })
+}
+
+fn clamp(j: &Json) -> Result<WindowClamp, String> {
+    let variant = match try!(string(j, "WindowClamp")) {
+        "bounce" => ClampVariant::Bounce,
+        "stop" => ClampVariant::Stop,
+        "remove" => ClampVariant::Remove,
+        "goal" => ClampVariant::Goal,
+        other => return Err(format!("Unknown WindowClamp '{}', expected bounce, stop, remove or goal", other))
+    };
+    Ok(WindowClamp { variant: variant })
+}
+
+fn binding(j: &Json, what: &str) -> Result<Binding, String> {
+    string(j, what).and_then(|b| b.parse().map_err(|e| format!("{}: {}", what, e)))
+}
+
+// `"W"` or `["W", "Up", "pad0:button11"]`.
+fn bindings(j: &Json, what: &str) -> Result<Vec<Binding>, String> {
+    match *j {
+        Json::Array(ref a) => a.iter().map(|b| binding(b, what)).collect(),
+        _ => binding(j, what).map(|b| vec![b])
+    }
+}
+
+fn layer(j: &Json) -> Result<CollisionLayer, String> {
+    match *j {
+        Json::String(ref s) => match &s[..] {
+            "gameplay" => Ok(CollisionLayer::gameplay()),
+            "effects" => Ok(CollisionLayer::effects()),
+            "debug" => Ok(CollisionLayer::debug()),
+            other => Err(format!("Unknown CollisionLayer '{}', expected gameplay, effects or debug", other))
+        },
+        _ => {
+            let o = try!(object(j, "CollisionLayer"));
+            try!(keys(o, &["category", "mask"], "CollisionLayer"));
+            let bits = |key| field(o, key, "CollisionLayer")
+                .and_then(|v| v.as_u64().ok_or_else(|| format!("CollisionLayer {} should be a whole number", key)));
+            Ok(CollisionLayer { category: try!(bits("category")) as u32, mask: try!(bits("mask")) as u32 })
+        }
+    }
+}
+
+impl Prefab {
+    pub fn parse(src: &str) -> Result<Prefab, String> {
+        let json = try!(Json::from_str(src).map_err(|e| format!("{}", e)));
+        Prefab::from_json(&json)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Prefab, String> {
+        let components = try!(object(json, "A prefab"));
+        let mut prefab = Prefab::default();
+        for (name, j) in components.iter() {
+            match &name[..] {
+                "Position" => {
+                    let o = try!(object(j, "Position"));
+                    try!(keys(o, &["from", "x", "y"], "Position"));
+                    let from = match o.get("from") {
+                        None => Anchor::Origin,
+                        Some(f) => match try!(string(f, "Position from")) {
+                            "origin" => Anchor::Origin,
+                            "center" => Anchor::Center,
+                            "left" => Anchor::Left,
+                            "right" => Anchor::Right,
+                            other => return Err(format!("Unknown Position from '{}', expected origin, center, left or right", other))
+                        }
+                    };
+                    prefab.position = Some(PositionSpec {
+                        from: from,
+                        x: try!(value(try!(field(o, "x", "Position")), "Position x")),
+                        y: try!(value(try!(field(o, "y", "Position")), "Position y"))
+                    });
+                }
+                "Velocity" => {
+                    let o = try!(object(j, "Velocity"));
+                    try!(keys(o, &["x", "y"], "Velocity"));
+                    prefab.velocity = Some((
+                        try!(value(try!(field(o, "x", "Velocity")), "Velocity x")),
+                        try!(value(try!(field(o, "y", "Velocity")), "Velocity y"))
+                    ));
+                }
+                "Shape" => prefab.shape = Some(try!(shape(j))),
+                "Color" => {
+                    let v = try!(numbers(j, 4, "Color"));
+                    prefab.color = Some([v[0] as f32, v[1] as f32, v[2] as f32, v[3] as f32]);
+                }
+                "WindowClamp" => prefab.clamp = Some(try!(clamp(j))),
+                "PlayerController" => {
+                    let o = try!(object(j, "PlayerController"));
+                    try!(keys(o, &["up", "down", "axis", "accel", "decel"], "PlayerController"));
+                    prefab.player_controller = Some(PlayerController {
+                        up: try!(bindings(try!(field(o, "up", "PlayerController")), "PlayerController up")),
+                        down: try!(bindings(try!(field(o, "down", "PlayerController")), "PlayerController down")),
+                        axis: match o.get("axis") {
+                            Some(a) => Some(try!(try!(string(a, "PlayerController axis")).parse()
+                                .map_err(|e| format!("PlayerController axis: {}", e)))),
+                            None => None
+                        },
+                        accel: match o.get("accel") {
+                            Some(a) => Some(try!(number(a, "PlayerController accel"))),
+                            None => None
+                        },
+                        decel: match o.get("decel") {
+                            Some(d) => Some(try!(number(d, "PlayerController decel"))),
+                            None => None
+                        }
+                    });
+                }
+                "PreviousPosition" => {
+                    let o = try!(object(j, "PreviousPosition"));
+                    try!(keys(o, &["x", "y"], "PreviousPosition"));
+                    prefab.previous_position = Some(Position {
+                        x: try!(number(try!(field(o, "x", "PreviousPosition")), "PreviousPosition x")),
+                        y: try!(number(try!(field(o, "y", "PreviousPosition")), "PreviousPosition y"))
+                    });
+                }
+                "AiController" => {
+                    // A difficulty's settings, then any of them overridden one by one.
+                    let o = try!(object(j, "AiController"));
+                    try!(keys(o, &["difficulty", "reaction", "max_speed", "error", "cooldown", "predict", "target"], "AiController"));
+                    let difficulty = match o.get("difficulty") {
+                        Some(d) => try!(try!(string(d, "AiController difficulty")).parse()
+                            .map_err(|e| format!("AiController difficulty: {}", e))),
+                        None => Difficulty::Medium
+                    };
+                    let mut ai = AiController::new(difficulty);
+                    for (key, v) in o.iter() {
+                        let what = format!("AiController {}", key);
+                        match &key[..] {
+                            "reaction" => ai.reaction = try!(number(v, &what)),
+                            "max_speed" => ai.max_speed = try!(number(v, &what)),
+                            "error" => ai.error = try!(number(v, &what)),
+                            "cooldown" => ai.cooldown = try!(number(v, &what)),
+                            "predict" => ai.predict = try!(boolean(v, &what)),
+                            "target" => ai.target = if v.is_null() { None } else { Some(try!(number(v, &what))) },
+                            _ => ()
+                        }
+                    }
+                    prefab.ai_controller = Some(ai);
+                }
+                "Shimmer" => {
+                    try!(keys(try!(object(j, "Shimmer")), &[], "Shimmer"));
+                    prefab.shimmer = true;
+                }
+                "Fade" => prefab.fade = Some(Fade(try!(number(j, "Fade")) as f32)),
+                "HitCount" => {
+                    let o = try!(object(j, "HitCount"));
+                    try!(keys(o, &["count", "recent"], "HitCount"));
+                    prefab.hit_count = Some(HitCount {
+                        count: match o.get("count") {
+                            Some(c) => try!(c.as_u64().ok_or_else(|| format!("HitCount count should be a whole number, found {}", c))) as u32,
+                            None => 0
+                        },
+                        recent: match o.get("recent") {
+                            Some(r) => try!(boolean(r, "HitCount recent")),
+                            None => false
+                        }
+                    });
+                }
+                "Mass" => prefab.mass = Some(Mass(try!(number(j, "Mass")))),
+                "CollisionLayer" => prefab.collision_layer = Some(try!(layer(j))),
+                "Text" => {
+                    let o = try!(object(j, "Text"));
+                    try!(keys(o, &["text", "size", "color", "offset"], "Text"));
+                    let color = match o.get("color") {
+                        Some(c) => try!(numbers(c, 4, "Text color")),
+                        None => vec![1.0, 1.0, 1.0, 1.0]
+                    };
+                    let offset = match o.get("offset") {
+                        Some(at) => try!(numbers(at, 2, "Text offset")),
+                        None => vec![0.0, 0.0]
+                    };
+                    prefab.text = Some(Text {
+                        text: try!(string(try!(field(o, "text", "Text")), "Text text")).to_string(),
+                        size: match o.get("size") {
+                            Some(size) => try!(size.as_u64().ok_or_else(|| format!("Text size should be a whole number, found {}", size))) as u32,
+                            None => 16
+                        },
+                        color: [color[0] as f32, color[1] as f32, color[2] as f32, color[3] as f32],
+                        offset: [offset[0], offset[1]]
+                    });
+                }
+                other => return Err(format!("Unknown component '{}'", other))
+            }
+        }
+        Ok(prefab)
+    }
+
+    /// The same format `from_json` reads.
+    pub fn to_json(&self) -> Json {
+        let mut o = Object::new();
+        let n = |v: f64| Json::F64(v);
+        let value = |v: &Value| match *v {
+            Value::Fixed(v) => Json::F64(v),
+            Value::Range { min, max, either_sign } => {
+                let mut r = Object::new();
+                r.insert("min".to_string(), Json::F64(min));
+                r.insert("max".to_string(), Json::F64(max));
+                r.insert("either_sign".to_string(), Json::Boolean(either_sign));
+                Json::Object(r)
+            }
+        };
+        let pairs = |fields: Vec<(&str, Json)>| {
+            Json::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+        };
+        if let Some(ref p) = self.position {
+            let from = match p.from {
+                Anchor::Origin => "origin",
+                Anchor::Center => "center",
+                Anchor::Left => "left",
+                Anchor::Right => "right"
+            };
+            o.insert("Position".to_string(), pairs(vec![
+                ("from", Json::String(from.to_string())), ("x", value(&p.x)), ("y", value(&p.y))
+            ]));
+        }
+        if let Some(ref p) = self.previous_position {
+            o.insert("PreviousPosition".to_string(), pairs(vec![("x", n(p.x)), ("y", n(p.y))]));
+        }
+        if let Some((ref x, ref y)) = self.velocity {
+            o.insert("Velocity".to_string(), pairs(vec![("x", value(x)), ("y", value(y))]));
+        }
+        if let Some(ref shape) = self.shape {
+            let mut fields = match shape.variant {
+                ShapeVariant::Point => vec![],
+                ShapeVariant::Circle(r) => vec![("circle", n(r))],
+                ShapeVariant::Square(w, h) => vec![("square", Json::Array(vec![n(w), n(h)]))],
+                ShapeVariant::Line(l) => vec![("line", Json::Array(l.iter().map(|v| n(*v)).collect()))]
+            };
+            if let Some(border) = shape.border {
+                fields.push(("border", n(border)));
+            }
+            let json = if shape.variant == ShapeVariant::Point && shape.border.is_none() {
+                Json::String("point".to_string())
+            } else {
+                pairs(fields)
+            };
+            o.insert("Shape".to_string(), json);
+        }
+        if let Some(color) = self.color {
+            o.insert("Color".to_string(), Json::Array(color.iter().map(|c| n(*c as f64)).collect()));
+        }
+        if let Some(ref clamp) = self.clamp {
+            let name = match clamp.variant {
+                ClampVariant::Bounce => "bounce",
+                ClampVariant::Stop => "stop",
+                ClampVariant::Remove => "remove",
+                ClampVariant::Goal => "goal"
+            };
+            o.insert("WindowClamp".to_string(), Json::String(name.to_string()));
+        }
+        if let Some(ref pc) = self.player_controller {
+            let names = |b: &Vec<Binding>| Json::Array(b.iter().map(|b| Json::String(b.name())).collect());
+            let mut fields = vec![("up", names(&pc.up)), ("down", names(&pc.down))];
+            if let Some(axis) = pc.axis {
+                fields.push(("axis", Json::String(axis.name())));
+            }
+            if let Some(accel) = pc.accel {
+                fields.push(("accel", n(accel)));
+            }
+            if let Some(decel) = pc.decel {
+                fields.push(("decel", n(decel)));
+            }
+            o.insert("PlayerController".to_string(), pairs(fields));
+        }
+        if let Some(ref ai) = self.ai_controller {
+            o.insert("AiController".to_string(), pairs(vec![
+                ("reaction", n(ai.reaction)),
+                ("max_speed", n(ai.max_speed)),
+                ("error", n(ai.error)),
+                ("predict", Json::Boolean(ai.predict)),
+                ("target", ai.target.map_or(Json::Null, Json::F64)),
+                ("cooldown", n(ai.cooldown))
+            ]));
+        }
+        if self.shimmer {
+            o.insert("Shimmer".to_string(), Json::Object(Object::new()));
+        }
+        if let Some(ref fade) = self.fade {
+            o.insert("Fade".to_string(), n(fade.0 as f64));
+        }
+        if let Some(ref hits) = self.hit_count {
+            o.insert("HitCount".to_string(), pairs(vec![
+                ("count", Json::U64(hits.count as u64)), ("recent", Json::Boolean(hits.recent))
+            ]));
+        }
+        if let Some(ref mass) = self.mass {
+            o.insert("Mass".to_string(), n(mass.0));
+        }
+        if let Some(layer) = self.collision_layer {
+            o.insert("CollisionLayer".to_string(), pairs(vec![
+                ("category", Json::U64(layer.category as u64)), ("mask", Json::U64(layer.mask as u64))
+            ]));
+        }
+        if let Some(ref text) = self.text {
+            o.insert("Text".to_string(), pairs(vec![
+                ("text", Json::String(text.text.clone())),
+                ("size", Json::U64(text.size as u64)),
+                ("color", Json::Array(text.color.iter().map(|c| n(*c as f64)).collect())),
+                ("offset", Json::Array(text.offset.iter().map(|v| n(*v)).collect()))
+            ]));
+        }
+        Json::Object(o)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Prefab, String> {
+        files::load(path.as_ref(), Prefab::parse)
+    }
+
+    /// Builds an entity, drawing any ranges from `Services::rng`. Anything with
+    /// a position also gets a matching previous position for interpolation.
+    pub fn spawn(&self, data: &mut DataHelper<Components, Services>) -> Entity {
+        let position = self.position.as_ref().map(|p| {
+            let (w, h) = (data.services.arena.view_w(), data.services.arena.view_h());
+            let (ax, ay) = match p.from {
+                Anchor::Origin => (0.0, 0.0),
+                Anchor::Center => (w / 2.0, h / 2.0),
+                Anchor::Left => (0.0, h / 2.0),
+                Anchor::Right => (w, h / 2.0)
+            };
+            let ref mut rng = data.services.rng;
+            Position { x: ax + p.x.draw(rng), y: ay + p.y.draw(rng) }
+        });
+        let velocity = self.velocity.as_ref().map(|&(ref x, ref y)| {
+            let ref mut rng = data.services.rng;
+            let x = x.draw(rng);
+            Velocity { x: x, y: y.draw(rng) }
+        });
+        data.create_entity(|entity: BuildData<Components>, c: &mut Components| {
+            if let Some(ref p) = position {
+                let previous = self.previous_position.clone().unwrap_or(p.clone());
+                c.previous_positions.add(&entity, previous);
+                c.positions.add(&entity, p.clone());
+            }
+            if let Some(ref v) = velocity {
+                c.velocities.add(&entity, v.clone());
+            }
+            if let Some(ref s) = self.shape {
+                c.shapes.add(&entity, s.clone());
+            }
+            if let Some(color) = self.color {
+                c.colors.add(&entity, color);
+            }
+            if let Some(ref clamp) = self.clamp {
+                c.clamps.add(&entity, clamp.clone());
+            }
+            if let Some(ref pc) = self.player_controller {
+                c.player_controllers.add(&entity, pc.clone());
+            }
+            if let Some(ref ai) = self.ai_controller {
+                c.ai_controllers.add(&entity, ai.clone());
+            }
+            if self.shimmer {
+                c.shimmers.add(&entity, Shimmer);
+            }
+            if let Some(ref fade) = self.fade {
+                c.fades.add(&entity, fade.clone());
+            }
+            if let Some(ref hits) = self.hit_count {
+                c.hit_counts.add(&entity, hits.clone());
+            }
+            if let Some(ref mass) = self.mass {
+                c.masses.add(&entity, mass.clone());
+            }
+            if let Some(layer) = self.collision_layer {
+                c.collision_layers.add(&entity, layer);
+            }
+            if let Some(ref text) = self.text {
+                c.texts.add(&entity, text.clone());
+            }
+        })
+    }
+}
+
+/// The prefabs `make_ball` and `make_player` build from.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Prefabs {
+    pub ball: Prefab,
+    pub left_paddle: Prefab,
+    pub right_paddle: Prefab
+}
+
+impl Default for Prefabs {
+    fn default() -> Prefabs {
+        Prefabs {
+            ball: Prefab::parse(include_str!("../../assets/prefabs/ball.json")).unwrap(),
+            left_paddle: Prefab::parse(include_str!("../../assets/prefabs/left_paddle.json")).unwrap(),
+            right_paddle: Prefab::parse(include_str!("../../assets/prefabs/right_paddle.json")).unwrap()
+        }
+    }
+}
+
+impl Prefabs {
+    /// Reads `ball.json`, `left_paddle.json` and `right_paddle.json` from `dir`.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Prefabs, String> {
+        let dir = dir.as_ref();
+        Ok(Prefabs {
+            ball: try!(Prefab::load(dir.join("ball.json"))),
+            left_paddle: try!(Prefab::load(dir.join("left_paddle.json"))),
+            right_paddle: try!(Prefab::load(dir.join("right_paddle.json")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(src: &str) -> String {
+        Prefab::parse(src).unwrap_err()
+    }
+
+    #[test]
+    fn bundled_prefabs_round_trip() {
+        let prefabs = Prefabs::default();
+        for prefab in &[prefabs.ball, prefabs.left_paddle, prefabs.right_paddle] {
+            assert_eq!(Prefab::from_json(&prefab.to_json()).as_ref(), Ok(prefab));
+        }
+    }
+
+    #[test]
+    fn unknown_keys_name_their_component() {
+        assert!(error(r#"{"Position": {"x": 1, "y": 2, "z": 3}}"#).contains("Unknown Position key 'z'"));
+        assert!(error(r#"{"Velocity": {"x": {"min": 1, "max": 2, "sign": true}, "y": 0}}"#).contains("Velocity x key 'sign'"));
+        assert!(error(r#"{"Shape": {"circle": 5, "colour": 1}}"#).contains("Unknown Shape key 'colour'"));
+        assert!(error(r#"{"HitCount": {"hits": 1}}"#).contains("Unknown HitCount key 'hits'"));
+        assert!(error(r#"{"AiController": {"reactoin": 0.1}}"#).contains("Unknown AiController key 'reactoin'"));
+        assert!(error(r#"{"Shimmer": {"speed": 1}}"#).contains("Shimmer"));
+        assert!(error(r#"{"Sparkle": {}}"#).contains("Unknown component 'Sparkle'"));
+    }
+
+    #[test]
+    fn wrong_types_name_their_component() {
+        assert!(error(r#"{"Velocity": {"x": {"min": 1, "max": 2, "either_sign": "yes"}, "y": 0}}"#)
+            .contains("Velocity x either_sign should be true or false"));
+        assert!(error(r#"{"HitCount": {"recent": 1}}"#).contains("HitCount recent should be true or false"));
+        assert!(error(r#"{"PlayerController": {"up": "Nope", "down": "S"}}"#).contains("PlayerController up: "));
+        assert!(error(r#"{"AiController": {"difficulty": "brutal"}}"#).contains("AiController difficulty: "));
+    }
+}
@@ -0,0 +1,137 @@
+use piston::input::RenderArgs;
+use std::path::Path;
+use ecs::components::Color;
+use ecs::render::{Draw, RenderTarget};
+
+// Samples per pixel along each axis for curved edges.
+const SUBSAMPLES: usize = 4;
+
+/// Draws frames into an RGBA image in memory, one pixel per window pixel,
+/// the same way on every machine. There's no font renderer here, so text is
+/// skipped, which also keeps it out of screenshots and golden frames.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8> // RGBA, top row first.
+}
+
+fn to_byte(v: f32) -> u8 {
+    (v.max(0.0).min(1.0) * 255.0 + 0.5) as u8
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32) -> Raster {
+        Raster { width: width, height: height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    /// Blends `color` over a pixel, `coverage` being how much of it is covered.
+    fn blend(&mut self, x: i64, y: i64, color: Color, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 || coverage <= 0.0 {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let a = color[3] * coverage.min(1.0);
+        let dst_a = self.pixels[i + 3] as f32 / 255.0;
+        let out_a = a + dst_a * (1.0 - a);
+        for c in 0..3 {
+            let dst = self.pixels[i + c] as f32 / 255.0;
+            let out = if out_a > 0.0 { (color[c] * a + dst * dst_a * (1.0 - a)) / out_a } else { 0.0 };
+            self.pixels[i + c] = to_byte(out);
+        }
+        self.pixels[i + 3] = to_byte(out_a);
+    }
+
+    // Pixels that might be touched by something within `[x1, y1]`-`[x2, y2]`.
+    fn span(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> (i64, i64, i64, i64) {
+        (x1.floor().max(0.0) as i64,
+         y1.floor().max(0.0) as i64,
+         x2.ceil().min(self.width as f64) as i64,
+         y2.ceil().min(self.height as f64) as i64)
+    }
+
+    // Covers each pixel by the fraction of its subsamples inside the shape.
+    fn fill<F: Fn(f64, f64) -> bool>(&mut self, color: Color, bounds: [f64; 4], inside: F) {
+        let (x0, y0, x1, y1) = self.span(bounds[0], bounds[1], bounds[2], bounds[3]);
+        let step = 1.0 / SUBSAMPLES as f64;
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let mut hits = 0;
+                for sy in 0..SUBSAMPLES {
+                    for sx in 0..SUBSAMPLES {
+                        let x = px as f64 + (sx as f64 + 0.5) * step;
+                        let y = py as f64 + (sy as f64 + 0.5) * step;
+                        if inside(x, y) {
+                            hits += 1;
+                        }
+                    }
+                }
+                self.blend(px, py, color, hits as f32 / (SUBSAMPLES * SUBSAMPLES) as f32);
+            }
+        }
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        let bytes = [to_byte(color[0]), to_byte(color[1]), to_byte(color[2]), to_byte(color[3])];
+        for pixel in self.pixels.chunks_mut(4) {
+            pixel.copy_from_slice(&bytes);
+        }
+    }
+
+    /// Exact coverage, so even a point smaller than a pixel shows up faintly.
+    pub fn rectangle(&mut self, color: Color, rect: [f64; 4]) {
+        let (left, top, right, bottom) = (rect[0], rect[1], rect[0] + rect[2], rect[1] + rect[3]);
+        let (x0, y0, x1, y1) = self.span(left, top, right, bottom);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let w = (right.min(px as f64 + 1.0) - left.max(px as f64)).max(0.0);
+                let h = (bottom.min(py as f64 + 1.0) - top.max(py as f64)).max(0.0);
+                self.blend(px, py, color, (w * h) as f32);
+            }
+        }
+    }
+
+    pub fn ellipse(&mut self, color: Color, rect: [f64; 4]) {
+        let (rx, ry) = (rect[2] / 2.0, rect[3] / 2.0);
+        if rx <= 0.0 || ry <= 0.0 {
+            return;
+        }
+        let (cx, cy) = (rect[0] + rx, rect[1] + ry);
+        self.fill(color, [rect[0], rect[1], rect[0] + rect[2], rect[1] + rect[3]], |x, y| {
+            let (dx, dy) = ((x - cx) / rx, (y - cy) / ry);
+            dx * dx + dy * dy <= 1.0
+        });
+    }
+
+    /// Everything within `radius` of the segment, so the ends are rounded.
+    pub fn line(&mut self, color: Color, l: [f64; 4], radius: f64) {
+        let (dx, dy) = (l[2] - l[0], l[3] - l[1]);
+        let len2 = dx * dx + dy * dy;
+        let bounds = [l[0].min(l[2]) - radius, l[1].min(l[3]) - radius,
+                      l[0].max(l[2]) + radius, l[1].max(l[3]) + radius];
+        self.fill(color, bounds, |x, y| {
+            let t = if len2 > 0.0 { (((x - l[0]) * dx + (y - l[1]) * dy) / len2).max(0.0).min(1.0) } else { 0.0 };
+            let (ex, ey) = (x - (l[0] + t * dx), y - (l[1] + t * dy));
+            ex * ex + ey * ey <= radius * radius
+        });
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        ::png::save(path, self.width, self.height, &self.pixels)
+    }
+}
+
+impl RenderTarget for Raster {
+    fn render(&mut self, _: &RenderArgs, origin: [f64; 2], frame: &[Draw]) {
+        let (x, y) = (origin[0], origin[1]);
+        for d in frame {
+            match *d {
+                Draw::Clear(color) => self.clear(color),
+                Draw::Rectangle(color, r) => self.rectangle(color, [r[0] + x, r[1] + y, r[2], r[3]]),
+                Draw::Ellipse(color, r) => self.ellipse(color, [r[0] + x, r[1] + y, r[2], r[3]]),
+                Draw::Line(color, l, radius) => self.line(color, [l[0] + x, l[1] + y, l[2] + x, l[3] + y], radius),
+                Draw::Text(..) => ()
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use graphics::{self, Ellipse, Line, Rectangle, Text, Transformed};
+use opengl_graphics::GlGraphics;
+use opengl_graphics::glyph_cache::GlyphCache;
+use piston::input::RenderArgs;
+use std::env;
+use std::path::{Path, PathBuf};
+use ecs::components::{Color, Position, ShapeVariant};
+use ecs::raster::Raster;
+
+/// One thing to draw, in view coordinates. Rectangles are `[x, y, w, h]`
+/// from their top left, ellipses fill the rectangle they're given and
+/// lines are `[x1, y1, x2, y2]` with a radius. Text is drawn at a size in
+/// pixels with its baseline starting at `[x, y]`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Draw {
+    Clear(Color),
+    Rectangle(Color, [f64; 4]),
+    Ellipse(Color, [f64; 4]),
+    Line(Color, [f64; 4], f64),
+    Text(Color, u32, [f64; 2], String)
+}
+
+/// How a shape at `p` is drawn.
+pub fn shape(variant: &ShapeVariant, p: &Position, color: Color) -> Draw {
+    match *variant {
+        ShapeVariant::Circle(rad) => Draw::Ellipse(color, graphics::ellipse::centered([p.x, p.y, rad, rad])),
+        ShapeVariant::Square(w, h) => Draw::Rectangle(color, graphics::rectangle::centered([p.x, p.y, w, h])),
+        ShapeVariant::Point => Draw::Rectangle(color, [p.x, p.y, 0.5, 0.5]),
+        ShapeVariant::Line(l) => Draw::Line(color, l, 1.0)
+    }
+}
+
+/// Anything DrawSystem can put a frame on. Everything but `Clear` is moved
+/// by `origin`, where the view's top left is in the window.
+pub trait RenderTarget {
+    fn render(&mut self, args: &RenderArgs, origin: [f64; 2], frame: &[Draw]);
+}
+
+pub const FONT: &'static str = "assets/fonts/DejaVuSansMono.ttf";
+
+/// Where `FONT` is: next to the executable if it was shipped that way,
+/// otherwise in the source tree it was built from. Never the working
+/// directory, which could be anywhere.
+pub fn font_path() -> PathBuf {
+    let beside = env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.join(FONT)));
+    match beside {
+        Some(ref path) if path.exists() => path.clone(),
+        _ => Path::new(env!("CARGO_MANIFEST_DIR")).join(FONT)
+    }
+}
+
+/// The window.
+pub struct Gl {
+    pub gl: GlGraphics,
+    pub glyphs: GlyphCache<'static>
+}
+
+impl Gl {
+    pub fn new(gl: GlGraphics) -> Result<Gl, String> {
+        let path = font_path();
+        let glyphs = try!(GlyphCache::new(&path).map_err(|e| format!("{}: {:?}", path.display(), e)));
+        Ok(Gl { gl: gl, glyphs: glyphs })
+    }
+}
+
+impl RenderTarget for Gl {
+    fn render(&mut self, args: &RenderArgs, origin: [f64; 2], frame: &[Draw]) {
+        let glyphs = &mut self.glyphs;
+        self.gl.draw(args.viewport(), |c, gl| {
+            let view = c.transform.trans(origin[0], origin[1]);
+            for d in frame {
+                match *d {
+                    Draw::Clear(color) => graphics::clear(color, gl),
+                    Draw::Rectangle(color, rect) => Rectangle::new(color).draw(rect, &c.draw_state, view, gl),
+                    Draw::Ellipse(color, rect) => Ellipse::new(color).draw(rect, &c.draw_state, view, gl),
+                    Draw::Line(color, line, radius) => Line::new(color, radius).draw(line, &c.draw_state, view, gl),
+                    Draw::Text(color, size, at, ref text) => {
+                        Text::new_color(color, size).draw(text, glyphs, &c.draw_state, view.trans(at[0], at[1]), gl);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// The targets the game's DrawSystem can be given: the window, or an image
+/// in memory for screenshots and machines without a GPU.
+pub enum Target {
+    Gl(Gl),
+    Raster(Raster)
+}
+
+impl RenderTarget for Target {
+    fn render(&mut self, args: &RenderArgs, origin: [f64; 2], frame: &[Draw]) {
+        match *self {
+            Target::Gl(ref mut gl) => gl.render(args, origin, frame),
+            Target::Raster(ref mut raster) => raster.render(args, origin, frame)
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use rand::Rng;
+use std::num::Wrapping as w;
+
+/// The same xorshift128 generator as `rand::XorShiftRng`, but with its state
+/// out in the open so snapshots can save and restore it. It is the same on
+/// every platform, unlike StdRng, so a seed always replays the same game.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SimRng {
+    pub state: [u32; 4]
+}
+
+impl SimRng {
+    // The state must never be all zeros, hence the fixed second half.
+    pub fn from_seed(seed: u64) -> SimRng {
+        SimRng { state: [seed as u32, (seed >> 32) as u32, 0x9e3779b9, 0x7f4a7c15] }
+    }
+}
+
+impl Rng for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        let x = w(self.state[0]);
+        let t = x ^ (x << 11);
+        self.state[0] = self.state[1];
+        self.state[1] = self.state[2];
+        self.state[2] = self.state[3];
+        let last = w(self.state[3]);
+        self.state[3] = (last ^ (last >> 19) ^ (t ^ (t >> 8))).0;
+        self.state[3]
+    }
+}
@@ -0,0 +1,289 @@
+use ecsrs::*;
+use ecsrs::system::EntityProcess;
+use rustc_serialize::json::{self, Json, Object};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use ecs::arena::{Arena, Wall};
+use ecs::components::*;
+use ecs::prefab::{Anchor, PositionSpec, Prefab, Value};
+use ecs::rng::SimRng;
+use ecs::scaffold::{in_order, Components, Services, Systems};
+use ecs::state::Phase;
+use files;
+
+/// Bumped whenever the file layout changes. Older files are refused rather
+/// than half loaded.
+pub const SNAPSHOT_VERSION: u64 = 3;
+
+/// Everything needed to carry on a game exactly where it was saved.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub seed: u64,
+    pub rng: SimRng,
+    pub score: Score,
+    pub phase: Phase,
+    pub rally: u32,
+    pub arena: Arena,
+    pub entities: Vec<Prefab>
+}
+
+/// Passive system, run with `process!`, that records every entity with a
+/// position. Everything the game has ever spawned has one.
+pub struct SnapshotSystem {
+    pub entities: Vec<Entity>,
+    pub prefabs: Vec<Prefab>
+}
+
+impl SnapshotSystem {
+    pub fn new() -> SnapshotSystem {
+        SnapshotSystem { entities: Vec::new(), prefabs: Vec::new() }
+    }
+}
+
+impl System for SnapshotSystem {
+    type Components = Components;
+    type Services = Services;
+    fn is_active(&self) -> bool { false }
+}
+
+impl EntityProcess for SnapshotSystem {
+    fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
+        self.entities.clear();
+        self.prefabs.clear();
+        for ref e in in_order(entities) {
+            let p = &data.positions[*e];
+            let velocity = if data.velocities.has(e) {
+                let v = &data.velocities[*e];
+                Some((Value::Fixed(v.x), Value::Fixed(v.y)))
+            } else {
+                None
+            };
+            self.entities.push(***e);
+            self.prefabs.push(Prefab {
+                position: Some(PositionSpec { from: Anchor::Origin, x: Value::Fixed(p.x), y: Value::Fixed(p.y) }),
+                previous_position: if data.previous_positions.has(e) { Some(data.previous_positions[*e].clone()) } else { None },
+                velocity: velocity,
+                shape: if data.shapes.has(e) { Some(data.shapes[*e].clone()) } else { None },
+                color: if data.colors.has(e) { Some(data.colors[*e]) } else { None },
+                clamp: if data.clamps.has(e) { Some(data.clamps[*e].clone()) } else { None },
+                player_controller: if data.player_controllers.has(e) { Some(data.player_controllers[*e].clone()) } else { None },
+                ai_controller: if data.ai_controllers.has(e) { Some(data.ai_controllers[*e].clone()) } else { None },
+                shimmer: data.shimmers.has(e),
+                fade: if data.fades.has(e) { Some(data.fades[*e].clone()) } else { None },
+                hit_count: if data.hit_counts.has(e) { Some(data.hit_counts[*e].clone()) } else { None },
+                mass: if data.masses.has(e) { Some(data.masses[*e].clone()) } else { None },
+                collision_layer: if data.collision_layers.has(e) { Some(data.collision_layers[*e]) } else { None },
+                text: if data.texts.has(e) { Some(data.texts[*e].clone()) } else { None }
+            });
+        }
+    }
+}
+
+fn side_json(side: Option<Side>) -> Json {
+    match side {
+        Some(Side::Left) => Json::String("left".to_string()),
+        Some(Side::Right) => Json::String("right".to_string()),
+        None => Json::Null
+    }
+}
+
+fn wall_json(wall: Wall) -> Json {
+    Json::String(match wall { Wall::Bounce => "bounce", Wall::Goal => "goal" }.to_string())
+}
+
+fn phase_json(phase: Phase) -> Json {
+    let mut o = Object::new();
+    let (name, side, time) = match phase {
+        Phase::Title => ("title", None, None),
+        Phase::Serve(t) => ("serve", None, Some(t)),
+        Phase::Rally => ("rally", None, None),
+        Phase::Scored(side, t) => ("scored", Some(side), Some(t)),
+        Phase::GameOver(side) => ("game_over", Some(side), None)
+    };
+    o.insert("name".to_string(), Json::String(name.to_string()));
+    if side.is_some() {
+        o.insert("side".to_string(), side_json(side));
+    }
+    if let Some(t) = time {
+        o.insert("time".to_string(), Json::F64(t));
+    }
+    Json::Object(o)
+}
+
+fn get<'a>(o: &'a Object, key: &str) -> Result<&'a Json, String> {
+    o.get(key).ok_or_else(|| format!("Snapshot is missing '{}'", key))
+}
+
+fn get_f64(o: &Object, key: &str) -> Result<f64, String> {
+    try!(get(o, key)).as_f64().ok_or_else(|| format!("Snapshot '{}' should be a number", key))
+}
+
+fn get_u64(o: &Object, key: &str) -> Result<u64, String> {
+    try!(get(o, key)).as_u64().ok_or_else(|| format!("Snapshot '{}' should be a whole number", key))
+}
+
+fn get_object<'a>(o: &'a Object, key: &str) -> Result<&'a Object, String> {
+    try!(get(o, key)).as_object().ok_or_else(|| format!("Snapshot '{}' should be an object", key))
+}
+
+impl Snapshot {
+    pub fn capture(world: &mut World<Systems>) -> Snapshot {
+        process!(world, snapshot);
+        let services = &world.data.services;
+        Snapshot {
+            seed: services.seed,
+            rng: services.rng.clone(),
+            score: services.score.clone(),
+            phase: services.state.phase,
+            rally: services.rally,
+            arena: services.arena.clone(),
+            entities: world.systems.snapshot.prefabs.clone()
+        }
+    }
+
+    /// Replaces every entity and the game state in `world` with the snapshot's.
+    pub fn restore(&self, world: &mut World<Systems>) {
+        // Entities are only added and removed when the queue is flushed, so
+        // flush first to catch any created since the last update, and again
+        // after, so the restored world can be saved or hashed straight away.
+        world.flush_queue();
+        process!(world, snapshot);
+        for e in world.systems.snapshot.entities.clone() {
+            world.data.remove_entity(e);
+        }
+        {
+            let services = &mut world.data.services;
+            services.seed = self.seed;
+            services.rng = self.rng.clone();
+            services.score = self.score.clone();
+            services.arena = self.arena.clone();
+            services.collisions.clear();
+            services.exits.clear();
+            services.rally = self.rally;
+            services.state.phase = self.phase;
+            services.state.paused = false;
+        }
+        for prefab in self.entities.iter() {
+            prefab.spawn(&mut world.data);
+        }
+        world.flush_queue();
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut score = Object::new();
+        score.insert("left".to_string(), Json::U64(self.score.left as u64));
+        score.insert("right".to_string(), Json::U64(self.score.right as u64));
+        score.insert("first_to".to_string(), Json::U64(self.score.win.first_to as u64));
+        score.insert("win_by".to_string(), Json::U64(self.score.win.win_by as u64));
+        score.insert("winner".to_string(), side_json(self.score.winner));
+
+        let a = &self.arena;
+        let mut arena = Object::new();
+        arena.insert("window_w".to_string(), Json::F64(a.window_w));
+        arena.insert("window_h".to_string(), Json::F64(a.window_h));
+        arena.insert("padding".to_string(), Json::F64(a.padding));
+        arena.insert("left".to_string(), wall_json(a.left));
+        arena.insert("right".to_string(), wall_json(a.right));
+        arena.insert("goal_size".to_string(), Json::F64(a.goal_size));
+        arena.insert("disp_fudge".to_string(), Json::F64(a.disp_fudge));
+
+        let mut o = Object::new();
+        o.insert("version".to_string(), Json::U64(SNAPSHOT_VERSION));
+        o.insert("seed".to_string(), Json::U64(self.seed));
+        o.insert("rng".to_string(), Json::Array(self.rng.state.iter().map(|s| Json::U64(*s as u64)).collect()));
+        o.insert("score".to_string(), Json::Object(score));
+        o.insert("phase".to_string(), phase_json(self.phase));
+        o.insert("rally".to_string(), Json::U64(self.rally as u64));
+        o.insert("arena".to_string(), Json::Object(arena));
+        o.insert("entities".to_string(), Json::Array(self.entities.iter().map(|p| p.to_json()).collect()));
+        Json::Object(o)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Snapshot, String> {
+        let o = try!(json.as_object().ok_or("A snapshot should be an object".to_string()));
+        let version = try!(get_u64(o, "version"));
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("Snapshot is version {}, this build reads version {}", version, SNAPSHOT_VERSION));
+        }
+
+        let state = try!(try!(get(o, "rng")).as_array().ok_or("Snapshot 'rng' should be a list".to_string()));
+        let state: Vec<u32> = state.iter().filter_map(|s| s.as_u64()).map(|s| s as u32).collect();
+        if state.len() != 4 {
+            return Err("Snapshot 'rng' should be 4 whole numbers".to_string());
+        }
+
+        let s = try!(get_object(o, "score"));
+        let winner = match try!(get(s, "winner")).as_string() {
+            Some("left") => Some(Side::Left),
+            Some("right") => Some(Side::Right),
+            _ => None
+        };
+        let score = Score {
+            left: try!(get_u64(s, "left")) as u32,
+            right: try!(get_u64(s, "right")) as u32,
+            win: try!(WinCondition::new(try!(get_u64(s, "first_to")) as u32,
+                                        try!(get_u64(s, "win_by")) as u32)),
+            goals: Vec::new(),
+            winner: winner
+        };
+
+        let p = try!(get_object(o, "phase"));
+        let side = || match get(p, "side").map(|s| s.as_string()) {
+            Ok(Some("left")) => Ok(Side::Left),
+            Ok(Some("right")) => Ok(Side::Right),
+            _ => Err("Snapshot phase 'side' should be left or right".to_string())
+        };
+        let phase = match try!(get(p, "name")).as_string() {
+            Some("title") => Phase::Title,
+            Some("serve") => Phase::Serve(try!(get_f64(p, "time"))),
+            Some("rally") => Phase::Rally,
+            Some("scored") => Phase::Scored(try!(side()), try!(get_f64(p, "time"))),
+            Some("game_over") => Phase::GameOver(try!(side())),
+            _ => return Err("Snapshot phase 'name' should be title, serve, rally, scored or game_over".to_string())
+        };
+
+        let a = try!(get_object(o, "arena"));
+        let wall = |key| get(a, key).and_then(|w| {
+            w.as_string().ok_or(format!("Snapshot arena '{}' should be a string", key)).and_then(|w| w.parse())
+        });
+        let arena = Arena {
+            window_w: try!(get_f64(a, "window_w")),
+            window_h: try!(get_f64(a, "window_h")),
+            padding: try!(get_f64(a, "padding")),
+            left: try!(wall("left")),
+            right: try!(wall("right")),
+            goal_size: try!(get_f64(a, "goal_size")),
+            disp_fudge: try!(get_f64(a, "disp_fudge")),
+            win: score.win.clone() // Saved with the score.
+        };
+
+        let list = try!(try!(get(o, "entities")).as_array().ok_or("Snapshot 'entities' should be a list".to_string()));
+        let mut entities = Vec::new();
+        for (i, e) in list.iter().enumerate() {
+            entities.push(try!(Prefab::from_json(e).map_err(|err| format!("entity {}: {}", i, err))));
+        }
+
+        Ok(Snapshot {
+            seed: try!(get_u64(o, "seed")),
+            rng: SimRng { state: [state[0], state[1], state[2], state[3]] },
+            score: score,
+            phase: phase,
+            rally: try!(get_u64(o, "rally")) as u32,
+            arena: arena,
+            entities: entities
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = format!("{}", json::as_pretty_json(&self.to_json()));
+        File::create(path)
+            .and_then(|mut f| f.write_all(text.as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Snapshot, String> {
+        files::load(path.as_ref(), |src| files::parse_json(src).and_then(|json| Snapshot::from_json(&json)))
+    }
+}
@@ -0,0 +1,109 @@
+use ecs::components::Side;
+
+/// Seconds counted down before each serve.
+pub const SERVE_TIME: f64 = 3.0;
+/// Seconds a point is shown for before the countdown to the next serve.
+pub const SCORED_TIME: f64 = 1.0;
+
+/// Where a game is up to. Timers are in seconds, counted down by each fixed
+/// update's dt, so every phase lasts just as long in a replay or at the other
+/// end of a network game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Title, // Waiting for a key before the first serve.
+    Serve(f64), // Seconds until the ball is served.
+    Rally,
+    Scored(Side, f64), // Who won the point, and seconds left showing it.
+    GameOver(Side) // Who won, waiting for a key to play again.
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameState {
+    pub phase: Phase,
+    pub paused: bool,
+    // Both ends of a network game have to agree on every change of phase, and
+    // only one of them would see a key, so neither can pause, and restarting
+    // goes through the input they exchange.
+    pub lockstep: bool,
+    pub stopped: Option<String> // Why the game can't go on, such as the other player leaving.
+}
+
+impl Default for GameState {
+    fn default() -> GameState {
+        GameState { phase: Phase::Serve(SERVE_TIME), paused: false, lockstep: false, stopped: None }
+    }
+}
+
+impl GameState {
+    /// Whether paddles and the ball should move this update.
+    pub fn running(&self) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+        match self.phase {
+            Phase::Title | Phase::GameOver(_) => false,
+            _ => !self.paused
+        }
+    }
+
+    pub fn can_pause(&self) -> bool {
+        !self.lockstep && self.running()
+    }
+
+    /// Counts down `dt`, returning true when the ball should be served.
+    pub fn tick(&mut self, dt: f64) -> bool {
+        if self.paused || self.stopped.is_some() {
+            return false;
+        }
+        match self.phase {
+            Phase::Serve(t) if t <= dt => {
+                self.phase = Phase::Rally;
+                true
+            }
+            Phase::Serve(t) => {
+                self.phase = Phase::Serve(t - dt);
+                false
+            }
+            Phase::Scored(_, t) if t <= dt => {
+                self.phase = Phase::Serve(SERVE_TIME);
+                false
+            }
+            Phase::Scored(side, t) => {
+                self.phase = Phase::Scored(side, t - dt);
+                false
+            }
+            _ => false
+        }
+    }
+
+    /// A point has been awarded, `winner` being set if it ended the game.
+    pub fn scored(&mut self, side: Side, winner: Option<Side>) {
+        self.phase = match winner {
+            Some(winner) => Phase::GameOver(winner),
+            None => Phase::Scored(side, SCORED_TIME)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serve_counts_down_in_seconds() {
+        let mut state = GameState::default();
+        assert!(!state.tick(SERVE_TIME / 2.0));
+        assert!(state.tick(SERVE_TIME / 2.0));
+        assert_eq!(state.phase, Phase::Rally);
+    }
+
+    #[test]
+    fn stopped_games_stay_put() {
+        let mut state = GameState::default();
+        state.stopped = Some("The other player left".to_string());
+        assert!(!state.running());
+        assert!(!state.can_pause());
+        assert!(!state.tick(SERVE_TIME * 2.0));
+        assert_eq!(state.phase, Phase::Serve(SERVE_TIME));
+    }
+}
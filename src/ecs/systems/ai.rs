@@ -0,0 +1,149 @@
+use ecsrs::*;
+use ecsrs::system::EntityProcess;
+use ecs::scaffold::{in_order, Components, Services};
+use ecs::components::{ClampVariant, Position, ShapeVariant, Velocity};
+use piston::input::UpdateEvent;
+use rand::Rng;
+
+pub struct AiSystem;
+
+impl System for AiSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+// Where a ball will cross `x`, folding its path back into the view at each
+// top or bottom wall bounce. None if the ball is moving away from `x`.
+fn predict_y(ball: &Position, v: &Velocity, r: f64, x: f64, view_height: f64) -> Option<f64> {
+    if v.x == 0.0 || (x - ball.x) * v.x < 0.0 {
+        return None;
+    }
+    let t = (x - ball.x) / v.x;
+    let lo = r;
+    let span = view_height - 2.0 * r;
+    if span <= 0.0 {
+        return Some(view_height / 2.0);
+    }
+    let y = (ball.y + v.y * t - lo) % (2.0 * span);
+    let y = if y < 0.0 { y + 2.0 * span } else { y };
+    Some(lo + if y > span { 2.0 * span - y } else { y })
+}
+
+impl EntityProcess for AiSystem {
+    fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
+        let event = data.services.event.clone();
+        let event =  event.borrow();
+        if let Some(update) = event.update_args() {
+            if !data.services.state.running() {
+                return;
+            }
+            let dt = update.dt;
+            let view_height = data.services.arena.view_h();
+            let evec = in_order(entities);
+            let balls: Vec<(Position, Velocity, f64)> = evec.iter()
+                .filter(|e| data.clamps.has(*e) && data.clamps[**e].variant == ClampVariant::Goal)
+                .map(|e| {
+                    let r = match data.shapes[*e].variant {
+                        ShapeVariant::Circle(r) => r,
+                        _ => 0.0
+                    };
+                    (data.positions[*e].clone(), data.velocities[*e].clone(), r)
+                })
+                .collect();
+            for e in evec.iter() {
+                if !data.ai_controllers.has(e) {
+                    continue;
+                }
+                let (x, y) = {
+                    let p = &data.positions[*e];
+                    (p.x, p.y)
+                };
+                let mut ai = data.ai_controllers[*e].clone();
+                ai.cooldown -= dt;
+                if ai.cooldown <= 0.0 {
+                    ai.cooldown = ai.reaction;
+                    // Chase whichever ball reaches our side first.
+                    let mut best: Option<(f64, f64)> = None;
+                    for &(ref bp, ref bv, r) in balls.iter() {
+                        let guess = if ai.predict {
+                            predict_y(bp, bv, r, x, view_height)
+                        } else if (x - bp.x) * bv.x > 0.0 {
+                            Some(bp.y)
+                        } else {
+                            None
+                        };
+                        if let Some(guess) = guess {
+                            let eta = (x - bp.x) / bv.x;
+                            if best.map_or(true, |(t, _)| eta < t) {
+                                best = Some((eta, guess));
+                            }
+                        }
+                    }
+                    let error = if ai.error > 0.0 {
+                        data.services.rng.gen_range(-ai.error, ai.error)
+                    } else {
+                        0.0
+                    };
+                    ai.target = Some(best.map_or(view_height / 2.0, |(_, y)| y) + error);
+                }
+                let vy = match ai.target {
+                    Some(target) if dt > 0.0 => {
+                        let v = (target - y) / dt;
+                        v.max(-ai.max_speed).min(ai.max_speed)
+                    }
+                    _ => 0.0
+                };
+                data.velocities[*e].y = vy;
+                data.ai_controllers[*e] = ai;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::predict_y;
+    use ecs::components::{Position, Velocity};
+
+    // A ball of radius 10 in a view 100 high moves between y = 10 and y = 90.
+    fn guess(y: f64, vx: f64, vy: f64) -> Option<f64> {
+        predict_y(&Position { x: 0.0, y: y }, &Velocity { x: vx, y: vy }, 10.0, 100.0, 100.0)
+    }
+
+    #[test]
+    fn straight_across() {
+        assert_eq!(guess(50.0, 100.0, 10.0), Some(60.0));
+    }
+
+    #[test]
+    fn one_bounce() {
+        assert_eq!(guess(50.0, 100.0, 60.0), Some(70.0));
+        // Upwards past the top wall, so the remainder is negative and wraps.
+        assert_eq!(guess(50.0, 100.0, -100.0), Some(70.0));
+    }
+
+    #[test]
+    fn two_bounces() {
+        assert_eq!(guess(50.0, 100.0, 150.0), Some(40.0));
+    }
+
+    #[test]
+    fn whole_trips_fold_away() {
+        // Ten round trips of 160 bring it back where it started.
+        assert_eq!(guess(50.0, 100.0, 1600.0), Some(50.0));
+        assert_eq!(guess(50.0, 100.0, -1600.0), Some(50.0));
+    }
+
+    #[test]
+    fn moving_away_or_still() {
+        assert_eq!(guess(50.0, -100.0, 0.0), None);
+        assert_eq!(guess(50.0, 0.0, 10.0), None);
+    }
+
+    #[test]
+    fn no_room_to_move_aims_at_the_middle() {
+        let (ball, v) = (Position { x: 0.0, y: 3.0 }, Velocity { x: 1.0, y: 1.0 });
+        assert_eq!(predict_y(&ball, &v, 10.0, 100.0, 20.0), Some(10.0));
+        assert_eq!(predict_y(&ball, &v, 10.0, 100.0, 15.0), Some(7.5));
+    }
+}
@@ -1,102 +1,164 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
-use ecs::scaffold::{Components, Services};
+use ecsrs::system::EntityProcess;
+use ecs::broadphase::{Bounds, Grid};
+use ecs::collide::{self, CollisionEvent, Contact};
+use ecs::components::CollisionLayer;
+use ecs::scaffold::{in_order, Components, Services};
 
-pub struct CollisionSystem;
+// Comfortably bigger than a paddle, so most things only sit in a few cells.
+const GRID_CELL: f64 = 64.0;
+
+pub struct CollisionSystem {
+    grid: Grid
+}
+
+impl CollisionSystem {
+    pub fn new() -> CollisionSystem {
+        CollisionSystem { grid: Grid::new(GRID_CELL) }
+    }
+}
 
 impl System for CollisionSystem {
     type Components = Components;
     type Services = Services;
 }
 
+// Lines never move. Player and AI controlled paddles push everything else
+// around, but block each other as equals.
+fn inverse_mass(data: &DataHelper<Components, Services>, e: &EntityData<Components>, other: &EntityData<Components>) -> f64 {
+    use ecs::components::ShapeVariant::Line;
+    if let Line(_) = data.shapes[*e].variant {
+        return 0.0;
+    }
+    let controlled = |e: &EntityData<Components>| {
+        data.player_controllers.has(e) || data.ai_controllers.has(e)
+    };
+    if controlled(e) && !controlled(other) {
+        return 0.0;
+    }
+    if data.masses.has(e) { 1.0 / data.masses[*e].0 } else { 1.0 }
+}
+
+// Separates the pair along the contact normal and applies an impulse to
+// their velocities. Circles bounce, everything else stops dead.
+fn resolve(data: &mut DataHelper<Components, Services>, a: &EntityData<Components>, b: &EntityData<Components>, contact: &Contact) {
+    use ecs::components::ShapeVariant::Circle;
+    let ima = inverse_mass(data, a, b);
+    let imb = inverse_mass(data, b, a);
+    let im = ima + imb;
+    if im == 0.0 {
+        return;
+    }
+    let n = contact.normal;
+    let depth = contact.depth;
+    {
+        let pa = &mut data.positions[*a];
+        pa.x -= n[0] * depth * ima / im;
+        pa.y -= n[1] * depth * ima / im;
+    }
+    {
+        let pb = &mut data.positions[*b];
+        pb.x += n[0] * depth * imb / im;
+        pb.y += n[1] * depth * imb / im;
+    }
+
+    let bouncy = |v: &::ecs::components::ShapeVariant| match *v { Circle(_) => true, _ => false };
+    let restitution = if bouncy(&data.shapes[*a].variant) || bouncy(&data.shapes[*b].variant) { 1.0 } else { 0.0 };
+    let va = data.velocities[*a].clone();
+    let vb = data.velocities[*b].clone();
+    let closing = (va.x - vb.x) * n[0] + (va.y - vb.y) * n[1];
+    // Already separating, e.g. still overlapping from last frame.
+    if closing > 0.0 {
+        let j = (1.0 + restitution) * closing / im;
+        {
+            let va = &mut data.velocities[*a];
+            va.x -= j * ima * n[0];
+            va.y -= j * ima * n[1];
+        }
+        let vb = &mut data.velocities[*b];
+        vb.x += j * imb * n[0];
+        vb.y += j * imb * n[1];
+    }
+}
+
+// Checks whether the ball will pass into the paddle during the coming move,
+// and if so bounces it at the moment of impact. MoveSystem runs after us and
+// adds a whole frame of the new velocity, so the position is wound back by the
+// part of the frame spent before the impact.
+// Returns the normal from paddle to ball and where they touched.
+fn sweep(data: &mut DataHelper<Components, Services>, s: &EntityData<Components>, c: &EntityData<Components>, dt: f64) -> Option<([f64; 2], [f64; 2])> {
+    use ecs::components::ShapeVariant::*;
+    let (w, h) = match data.shapes[*s].variant { Square(w, h) => (w, h), _ => return None };
+    let r = match data.shapes[*c].variant { Circle(r) => r, _ => return None };
+    let ps = data.positions[*s].clone();
+    let pc = data.positions[*c].clone();
+    let vs = data.velocities[*s].clone();
+    let vc = data.velocities[*c].clone();
+    let rel = [vc.x - vs.x, vc.y - vs.y];
+    let motion = [rel[0] * dt, rel[1] * dt];
+    let (t, n) = match collide::sweep_circle_aabb([pc.x, pc.y], motion, r, [ps.x, ps.y], [w, h]) {
+        Some(hit) => hit,
+        None => return None
+    };
+    let closing = rel[0] * n[0] + rel[1] * n[1];
+    if closing >= 0.0 {
+        return None;
+    }
+    let v = [vc.x - 2.0 * closing * n[0], vc.y - 2.0 * closing * n[1]];
+    let impact = [pc.x + vc.x * dt * t, pc.y + vc.y * dt * t];
+    {
+        let p = &mut data.positions[*c];
+        p.x = impact[0] - v[0] * dt * t;
+        p.y = impact[1] - v[1] * dt * t;
+    }
+    let velocity = &mut data.velocities[*c];
+    velocity.x = v[0];
+    velocity.y = v[1];
+    Some((n, [impact[0] - n[0] * r, impact[1] - n[1] * r]))
+}
+
 impl EntityProcess for CollisionSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
-        use vecmath::*;
         use ecs::components::ShapeVariant::*;
-        use piston::input::{ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
+        use piston::input::UpdateEvent;
 
         let event = data.services.event.clone();
         let event =  event.borrow();
         if let Some(update) = event.update_args() {
-            let evec: Vec<EntityData<Components>> = entities.collect();
-            for (i, e1) in evec.iter().enumerate() {
-                let shape1 = data.shapes[*e1].clone();
-
-                let targets = evec.iter().skip(i+1);
-                for e2 in targets {
-                    let p1 = data.positions[*e1].clone();
-                    let p2 = data.positions[*e2].clone();
-                    let dist2 = {
-                        let d2 = (p1.x - p2.x) * (p1.x - p2.x) + (p1.y - p2.y) * (p1.y - p2.y);
-                        d2
-                    };
-                    let shape2 = data.shapes[*e2].clone();
-                    match (e1, e2, p1, p2, &shape1.variant, &shape2.variant) {
-                        (_, _, _, _, &Circle(r1), &Circle(r2)) => {
-                            if (r1 + r2)*(r1 + r2) > dist2 {
-                            }
-                        }
-                        (s, c, square_center, circle_center, &Square(w,h), &Circle(r)) |
-                        (c, s, circle_center, square_center, &Circle(r), &Square(w, h)) => {
-                            let mut px = circle_center.clone();
-                            if circle_center.x < square_center.x - w { px.x = square_center.x - w; }
-                            if circle_center.x > square_center.x + w { px.x = square_center.x + w; }
-                            if circle_center.y < square_center.y - h { px.y = square_center.y - h; }
-                            if circle_center.y > square_center.y + h { px.y = square_center.y + h; }
-
-                            let center: Vector2<f64> = [circle_center.x, circle_center.y];
-                            let px: Vector2<f64> = [px.x, px.y];
-                            let aligned_r = vec2_scale(vec2_normalized(vec2_sub(px, center)), r);
-                            let cedge = vec2_add(center, aligned_r);
-                            let disp = vec2_scale(vec2_sub(px, cedge), ::DISP_FUDGE);
-                            let neg = vec2_scale(disp, -1.0);
-
-                            let dx = px[0] - center[0];
-                            let dy = px[1] - center[1];
-                            let dist2 = dx * dx + dy * dy;
-
-                            if r*r > dist2 {
-                                if data.hit_counts.has(c) {
-                                    if !data.hit_counts[*c].recent {
-                                        data.hit_counts[*c].count += 1;
-                                        data.hit_counts[*c].recent = true;
-                                    }
-                                }
-                                if data.hit_counts.has(s) {
-                                    if !data.hit_counts[*s].recent {
-                                        data.hit_counts[*s].count += 1;
-                                        data.hit_counts[*s].recent = true;
-                                    }
-                                }
-                                *(&mut(data.positions[*c].x)) += disp[0];
-                                *(&mut(data.positions[*c].y)) += disp[1];
-                                *(&mut(data.velocities[*s].x)) *= 0.5;
-                                *(&mut(data.velocities[*s].y)) *= 0.5;
-                                super::super::debug::ghost(data, c);
-                                super::super::debug::line(data, [center[0], center[1], center[0] + disp[0], center[1] + disp[1]], 0.01);
-
-                                let v = &mut data.velocities[*c];
-                                if disp[0] > 0.0 {
-                                    if v.x < 0.0 { v.x *= -1.0 }
-                                } else if disp[0] < 0.0 {
-                                    if v.x > 0.0 { v.x *= -1.0 }
-                                }
-                                if disp[1] > 0.0 {
-                                    if v.y < 0.0 { v.y *= -1.0 }
-                                } else if disp[1] < 0.0 {
-                                    if v.y > 0.0 { v.y *= -1.0 }
-                                }
-                            } else {
-                                if data.hit_counts.has(c) {
-                                    data.hit_counts[*c].recent = false;
-                                }
-                                if data.hit_counts.has(s) {
-                                    data.hit_counts[*s].recent = false;
-                                }
-                            }
-                        }
-                        _ => ()
+            data.services.collisions.clear();
+            if !data.services.state.running() {
+                return;
+            }
+            let evec = in_order(entities);
+            let shapes: Vec<_> = evec.iter().map(|e| data.shapes[*e].variant.clone()).collect();
+            // Cover the coming move too, so sweeps find what they'd tunnel through.
+            let bounds: Vec<Bounds> = evec.iter().zip(shapes.iter()).map(|(e, shape)| {
+                let v = &data.velocities[*e];
+                Bounds::of(&data.positions[*e], shape).swept([v.x * update.dt, v.y * update.dt])
+            }).collect();
+            let layers: Vec<CollisionLayer> = evec.iter().map(|e| data.collision_layers[*e]).collect();
+            for (i, j) in self.grid.pairs(&bounds, |i, j| layers[i].collides(&layers[j])) {
+                let (e1, e2) = (&evec[i], &evec[j]);
+                let (shape1, shape2) = (&shapes[i], &shapes[j]);
+                let p1 = data.positions[*e1].clone();
+                let p2 = data.positions[*e2].clone();
+                let hit = if let Some(contact) = collide::contact(&p1, shape1, &p2, shape2) {
+                    resolve(data, e1, e2, &contact);
+                    Some(CollisionEvent { a: ***e1, b: ***e2, normal: contact.normal, depth: contact.depth, point: contact.point })
+                } else {
+                    // Only balls are fast enough to pass clean through a paddle in one update.
+                    match (shape1, shape2) {
+                        (&Square(..), &Circle(_)) => sweep(data, e1, e2, update.dt).map(|(n, point)| {
+                            CollisionEvent { a: ***e1, b: ***e2, normal: n, depth: 0.0, point: point }
+                        }),
+                        (&Circle(_), &Square(..)) => sweep(data, e2, e1, update.dt).map(|(n, point)| {
+                            CollisionEvent { a: ***e1, b: ***e2, normal: [-n[0], -n[1]], depth: 0.0, point: point }
+                        }),
+                        _ => None
                     }
+                };
+                if let Some(hit) = hit {
+                    data.services.collisions.push(hit);
                 }
             }
         }
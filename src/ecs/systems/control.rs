@@ -1,7 +1,12 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
+use ecsrs::system::EntityProcess;
+use ecs::components::PlayerController;
+use ecs::input::PaddleInput;
 use ecs::scaffold::{Components, Services};
 
+const PADDLE_V: f64 = 800.0;
+const DEAD_ZONE: f64 = 0.15; // Sticks rarely come to rest at exactly 0.
+
 pub struct ControlSystem;
 
 impl System for ControlSystem {
@@ -9,37 +14,135 @@ impl System for ControlSystem {
     type Services = Services;
 }
 
+// Moves `from` towards `to` by no more than `by`.
+fn approach(from: f64, to: f64, by: f64) -> f64 {
+    if from < to { (from + by).min(to) } else { (from - by).max(to) }
+}
+
+// A paddle's next vertical velocity, at `y` and moving at `vy`.
+fn velocity(paddle: &PaddleInput, controller: &PlayerController, y: f64, vy: f64, dt: f64) -> f64 {
+    // Held keys win, then a pushed stick, then chasing the mouse.
+    let wanted = if paddle.up != paddle.down {
+        if paddle.up { -PADDLE_V } else { PADDLE_V }
+    } else if paddle.push.abs() >= DEAD_ZONE {
+        paddle.push * PADDLE_V
+    } else if let Some(mouse_y) = paddle.mouse_y {
+        ((mouse_y - y) / dt).max(-PADDLE_V).min(PADDLE_V)
+    } else {
+        0.0
+    };
+
+    let speeding_up = wanted.abs() > vy.abs() && wanted * vy >= 0.0;
+    let rate = if speeding_up { controller.accel } else { controller.decel };
+    match rate {
+        Some(rate) => approach(vy, wanted, rate * dt),
+        None => wanted
+    }
+}
+
 impl EntityProcess for ControlSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
-        use piston::input::Button::Keyboard;
-        use piston::input::{ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
-        const PADDLE_V: f64 = 800.0;
+        use piston::input::UpdateEvent;
+        let event = data.services.event.clone();
+        let event =  event.borrow();
+        let update = match event.update_args() {
+            Some(update) => update,
+            None => return
+        };
+        if !data.services.state.running() {
+            return;
+        }
+        let input = data.services.input.clone();
+        let net_input = data.services.net_input;
+        let padding = data.services.arena.padding;
+        let half_w = data.services.arena.view_w() / 2.0;
         for ref e in entities {
-            let (vx, vy) = {
-                let v = &data.velocities[*e];
-                (v.x, v.y)
+            let controller = data.player_controllers[*e].clone();
+            let (x, y) = {
+                let p = &data.positions[*e];
+                (p.x, p.y)
             };
-            let event = data.services.event.clone();
-            let event =  event.borrow();
-            let (up, down) = {
-                let controller  = &(data.player_controllers[*e]);
-                (Keyboard(controller.up), Keyboard(controller.down))
+            let vy = data.velocities[*e].y;
+
+            let paddle = match net_input {
+                Some(inputs) => inputs[if x < half_w { 0 } else { 1 }],
+                None => PaddleInput::sample(&input, &controller, padding)
             };
-            let velocity = &mut(data.velocities[*e]);
-            event.press(|button| {
-                if button == up {
-                    velocity.y = -PADDLE_V;
-                } else if button == down {
-                    velocity.y = PADDLE_V;
-                }
-            });
-            event.release(|button| {
-                if button == up
-                || button == down {
-                    velocity.y = 0.0;
-                }
-            });
+            data.velocities[*e].y = velocity(&paddle, &controller, y, vy, update.dt);
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{velocity, PADDLE_V};
+    use ecs::components::{Binding, PlayerController};
+    use ecs::input::{InputState, PaddleInput};
+    use piston::input::{Button, Event, Input, Key};
+
+    fn keys(accel: Option<f64>, decel: Option<f64>) -> PlayerController {
+        PlayerController {
+            up: vec![Binding::Key(Key::Up)],
+            down: vec![Binding::Key(Key::Down)],
+            axis: None,
+            accel: accel,
+            decel: decel
+        }
+    }
+
+    fn feed(input: &mut InputState, event: Input) {
+        input.event(&Event::Input(event));
+    }
+
+    #[test]
+    fn releasing_one_key_goes_back_to_the_other() {
+        let controller = keys(None, None);
+        let mut input = InputState::default();
+        let v = |input: &InputState| velocity(&PaddleInput::sample(input, &controller, 0.0), &controller, 0.0, 0.0, 0.01);
+        feed(&mut input, Input::Press(Button::Keyboard(Key::Up)));
+        assert_eq!(v(&input), -PADDLE_V);
+        feed(&mut input, Input::Press(Button::Keyboard(Key::Down)));
+        assert_eq!(v(&input), 0.0);
+        feed(&mut input, Input::Release(Button::Keyboard(Key::Down)));
+        assert_eq!(v(&input), -PADDLE_V);
+        feed(&mut input, Input::Focus(false));
+        assert_eq!(v(&input), 0.0);
+    }
+
+    #[test]
+    fn speeds_up_and_slows_down_at_their_own_rates() {
+        let controller = keys(Some(4000.0), Some(8000.0));
+        let down = PaddleInput { down: true, ..PaddleInput::default() };
+        let up = PaddleInput { up: true, ..PaddleInput::default() };
+        let still = PaddleInput::default();
+        let dt = 0.05;
+        // 200 px/s faster each step up to full speed...
+        let mut vy = 0.0;
+        for want in &[200.0, 400.0, 600.0, 800.0, 800.0] {
+            vy = velocity(&down, &controller, 0.0, vy, dt);
+            assert_eq!(vy, *want);
+        }
+        // ...and 400 px/s slower once let go, stopping at 0.
+        for want in &[400.0, 0.0, 0.0] {
+            vy = velocity(&still, &controller, 0.0, vy, dt);
+            assert_eq!(vy, *want);
+        }
+        // Turning round brakes first, then speeds up the other way.
+        vy = 200.0;
+        vy = velocity(&up, &controller, 0.0, vy, dt);
+        assert_eq!(vy, -200.0);
+        vy = velocity(&up, &controller, 0.0, vy, dt);
+        assert_eq!(vy, -400.0);
+    }
+
+    #[test]
+    fn keys_then_stick_then_mouse() {
+        let controller = keys(None, None);
+        let both = PaddleInput { up: true, push: 0.5, mouse_y: Some(100.0), ..PaddleInput::default() };
+        assert_eq!(velocity(&both, &controller, 0.0, 0.0, 0.5), -PADDLE_V);
+        let stick = PaddleInput { push: 0.5, mouse_y: Some(100.0), ..PaddleInput::default() };
+        assert_eq!(velocity(&stick, &controller, 0.0, 0.0, 0.5), 0.5 * PADDLE_V);
+        let resting = PaddleInput { push: 0.1, mouse_y: Some(100.0), ..PaddleInput::default() };
+        assert_eq!(velocity(&resting, &controller, 0.0, 0.0, 0.5), 200.0);
+    }
+}
@@ -0,0 +1,22 @@
+use ecsrs::*;
+use piston::input::UpdateEvent;
+use ecs::scaffold::{Components, Services};
+
+/// Ages what's left on the debug overlay, before any system this update
+/// adds to it.
+pub struct DebugSystem;
+
+impl System for DebugSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+impl Process for DebugSystem {
+    fn process(&mut self, data: &mut DataHelper<Components, Services>) {
+        let event = data.services.event.clone();
+        let event = event.borrow();
+        if event.update_args().is_some() {
+            data.services.debug.tick();
+        }
+    }
+}
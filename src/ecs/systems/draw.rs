@@ -1,104 +1,68 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
-use ecs::debug;
-use ecs::scaffold::{Components, Services};
+use ecsrs::system::EntityProcess;
+use ecs::broadphase::Bounds;
+use ecs::render::{shape, Draw, RenderTarget};
+use ecs::scaffold::{in_order, Components, Services};
+use ecs::components::Position;
 
-use std::cell::RefCell;
-use std::cell::Cell;
-use std::rc::Rc;
-use opengl_graphics::GlGraphics;
-
-pub struct DrawSystem {
-    pub gl: Option<RefCell<GlGraphics>>,
+pub struct DrawSystem<T> {
+    pub target: Option<T>,
 }
 
 
-impl System for DrawSystem {
+impl<T: RenderTarget> System for DrawSystem<T> {
     type Components = Components;
     type Services = Services;
 }
 
-impl EntityProcess for DrawSystem {
+impl<T: RenderTarget> EntityProcess for DrawSystem<T> {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
-        use ::graphics;
-        use graphics::*;
-//        use graphics::types::{Color};
-        use ecs::components::ShapeVariant as shape;
-        use piston::input::{ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
-        let pad = ::WINDOW_PADDING;
-        if let Some(ref gl_cell) = self.gl {
-            let mut gl = gl_cell.borrow_mut();
+        use piston::input::RenderEvent;
+        if let Some(ref mut target) = self.target {
             let event = data.services.event.borrow();
             if let Some(render) = event.render_args() {
-                let view_width = render.width as f64 - 2.0 * pad;
-                let view_height = render.height as f64 - 2.0 * pad;
-                gl.draw(render.viewport(), |c, gl| { // viewport
-                    graphics::clear([0.2, 0.2, 0.2, 1.0], gl);
-                    graphics::rectangle([0.0, 1.0, 0.0, 1.0],
-                                        [0.0, 0.0, view_width, view_height],
-                                        c.transform,
-                                        gl);
-                    graphics::rectangle([0.0, 0.0, 0.0, 1.0],
-                                        [1.0, 1.0, view_width - 2.0,  view_height - 2.0],
-                                        c.transform,
-                                        gl);
-                    for ref e in entities {
-                        let position = &data.positions[*e];
-                        let shape = &data.shapes[*e];
-                        let color = data.colors[*e];
-                        match shape.variant {
-                            shape::Circle(rad) => {
-                                let circle = Ellipse::new(color);
-                                circle.draw(
-                                    graphics::ellipse::centered([
-                                        position.x,
-                                        position.y,
-                                        rad, rad
-                                    ]),
-                                    &c.draw_state,
-                                    c.transform,
-                                    gl
-                                );
-                            }
-                            shape::Square(w, h) => {
-                                let square = Rectangle::new(color);
-                                square.draw(
-                                    graphics::rectangle::centered([
-                                        position.x,
-                                        position.y,
-                                        w, h
-                                    ]),
-                                    &c.draw_state,
-                                    c.transform,
-                                    gl
-                                );
-                            },
-                            shape::Point => {
-                                let pixel = Rectangle::new(color);
-                                pixel.draw(
-                                    [
-                                        position.x,
-                                        position.y,
-                                        0.5, 0.5
-                                    ],
-                                    &c.draw_state,
-                                    c.transform,
-                                    gl
-                                );
-                            }
-                            shape::Line(l) => {
-                                let line = Line::new(color, 1.0);
-                                line.draw(
-                                    l,
-                                    &c.draw_state,
-                                    c.transform,
-                                    gl
-                                );
-                            }
+                let view_width = data.services.arena.view_w();
+                let view_height = data.services.arena.view_h();
+                let mut frame = vec![
+                    Draw::Clear([0.2, 0.2, 0.2, 1.0]),
+                    Draw::Rectangle([0.0, 1.0, 0.0, 1.0], [0.0, 0.0, view_width, view_height]),
+                    Draw::Rectangle([0.0, 0.0, 0.0, 1.0], [1.0, 1.0, view_width - 2.0,  view_height - 2.0])
+                ];
+                let mut texts = Vec::new();
+                for ref e in in_order(entities) {
+                    let position = if data.previous_positions.has(e) {
+                        let (from, to) = (&data.previous_positions[*e], &data.positions[*e]);
+                        let alpha = data.services.alpha;
+                        Position {
+                            x: from.x + (to.x - from.x) * alpha,
+                            y: from.y + (to.y - from.y) * alpha
                         }
+                    } else {
+                        data.positions[*e].clone()
+                    };
+                    if data.shapes.has(e) && data.colors.has(e) {
+                        frame.push(shape(&data.shapes[*e].variant, &position, data.colors[*e]));
+                        let bounds = Bounds::of(&position, &data.shapes[*e].variant);
+                        data.services.debug.bounds(&bounds);
+                    }
+                    if data.velocities.has(e) {
+                        let v = [data.velocities[*e].x, data.velocities[*e].y];
+                        data.services.debug.velocity(&position, v);
+                    }
+                    if !data.huds.has(e) {
+                        data.services.debug.id(&position, (***e).id());
+                    }
+                    if data.texts.has(e) && !data.texts[*e].text.is_empty() {
+                        let t = &data.texts[*e];
+                        let at = [position.x + t.offset[0], position.y + t.offset[1]];
+                        texts.push(Draw::Text(t.color, t.size, at, t.text.clone()));
                     }
-                });
+                }
+                frame.extend(data.services.debug.drain());
+                frame.extend(texts); // Over everything else.
+                let padding = data.services.arena.padding;
+                target.render(&render, [padding, padding], &frame);
             } // if let Some(render)
-        } //gl cell
+        } // target
     }
 }
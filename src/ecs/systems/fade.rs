@@ -1,7 +1,7 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
+use ecsrs::system::EntityProcess;
 use ecs::scaffold::{Components, Services};
-use piston::input::{ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
+use piston::input::UpdateEvent;
 
 pub struct FadeSystem;
 
@@ -14,7 +14,10 @@ impl EntityProcess for FadeSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
         let event = data.services.event.clone();
         let event =  event.borrow();
-        if let Some(update) = event.update_args() {
+        if event.update_args().is_some() {
+            if data.services.state.paused {
+                return;
+            }
             for ref e in entities {
                 let f = data.fades[*e].0;
                 let delete = {
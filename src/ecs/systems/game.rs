@@ -1,10 +1,53 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
-use ecs::debug;
-use ecs::scaffold::{Components, Services};
-use piston::input::{Event, ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
+use ecsrs::system::EntityProcess;
+use ecs::render;
+use ecs::scaffold::{in_order, Components, Services};
+use piston::input::UpdateEvent;
+use std::collections::HashMap;
 
-pub struct GameSystem;
+/// Classic Pong english: where the ball meets a paddle's face decides its
+/// outgoing angle, and every hit speeds it up a little.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Deflection {
+    pub max_angle: f64, // Radians from horizontal when hit on the paddle's very end.
+    pub spin: f64, // Radians added per px/s of paddle velocity, 0 to ignore paddle motion.
+    pub speed_up: f64, // Multiplies ball speed on each hit.
+    pub max_speed: f64
+}
+
+impl Default for Deflection {
+    fn default() -> Deflection {
+        Deflection {
+            max_angle: ::std::f64::consts::PI / 3.0,
+            spin: 0.0003,
+            speed_up: 1.05,
+            max_speed: 1200.0
+        }
+    }
+}
+
+impl Deflection {
+    // `n` points from the paddle to the ball, `offset` runs from -1 at the
+    // paddle's top to 1 at its bottom.
+    fn deflect(&self, v: [f64; 2], n: [f64; 2], offset: f64, paddle_vy: f64) -> [f64; 2] {
+        let angle = (offset * self.max_angle + paddle_vy * self.spin)
+            .max(-self.max_angle)
+            .min(self.max_angle);
+        let speed = ((v[0] * v[0] + v[1] * v[1]).sqrt() * self.speed_up).min(self.max_speed);
+        let out = if n[0] < 0.0 { -1.0 } else { 1.0 };
+        [out * speed * angle.cos(), speed * angle.sin()]
+    }
+}
+
+pub struct GameSystem {
+    pub deflection: Deflection
+}
+
+impl GameSystem {
+    pub fn new() -> GameSystem {
+        GameSystem { deflection: Deflection::default() }
+    }
+}
 
 impl System for GameSystem {
     type Components = Components;
@@ -13,11 +56,111 @@ impl System for GameSystem {
 
 impl EntityProcess for GameSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
-        for ref e in entities {
-            let hits = data.hit_counts[*e].clone();
-            let col = &mut data.colors[*e];
-            col[0] = hits.count as f32 * 0.1;
+        use ecs::components::ShapeVariant::*;
+        let event = data.services.event.clone();
+        let event =  event.borrow();
+        if event.update_args().is_some() {
+            let evec = in_order(entities);
+            let index: HashMap<Entity, usize> = evec.iter().enumerate().map(|(i, e)| (***e, i)).collect();
+            let mut touched = vec![false; evec.len()];
+
+            for hit in data.services.collisions.clone() {
+                let (a, b) = match (index.get(&hit.a), index.get(&hit.b)) {
+                    (Some(&a), Some(&b)) => (a, b),
+                    _ => continue // Removed since it was reported.
+                };
+                touched[a] = true;
+                touched[b] = true;
+                data.services.debug.normal(hit.point, hit.normal);
+
+                // A paddle hitting the ball loses momentum.
+                let (ea, eb) = (&evec[a], &evec[b]);
+                let paddle_ball = match (&data.shapes[*ea].variant, &data.shapes[*eb].variant) {
+                    (&Square(..), &Circle(_)) => Some((ea, eb, hit.normal)),
+                    (&Circle(_), &Square(..)) => Some((eb, ea, [-hit.normal[0], -hit.normal[1]])),
+                    _ => None
+                };
+                if let Some((s, c, n)) = paddle_ball {
+                    // Only off the paddle's face, and only once per touch.
+                    let fresh = !data.hit_counts.has(s) || !data.hit_counts[*s].recent;
+                    if let Square(_, h) = data.shapes[*s].variant {
+                        if fresh && n[0].abs() > n[1].abs() && h > 0.0 {
+                            let offset = ((hit.point[1] - data.positions[*s].y) / h).max(-1.0).min(1.0);
+                            let paddle_vy = data.velocities[*s].y;
+                            let v = data.velocities[*c].clone();
+                            let v = self.deflection.deflect([v.x, v.y], n, offset, paddle_vy);
+                            {
+                                let velocity = &mut data.velocities[*c];
+                                velocity.x = v[0];
+                                velocity.y = v[1];
+                            }
+                            data.services.rally += 1;
+                        }
+                    }
+                    *(&mut(data.velocities[*s].x)) *= 0.5;
+                    *(&mut(data.velocities[*s].y)) *= 0.5;
+                    if data.colors.has(c) {
+                        let ghost = render::shape(&data.shapes[*c].variant, &data.positions[*c], data.colors[*c]);
+                        data.services.debug.ghost(ghost);
+                    }
+                }
+            }
+
+            for (e, touched) in evec.iter().zip(touched) {
+                if !data.hit_counts.has(e) {
+                    continue;
+                }
+                if !touched {
+                    data.hit_counts[*e].recent = false;
+                } else if !data.hit_counts[*e].recent {
+                    data.hit_counts[*e].count += 1;
+                    data.hit_counts[*e].recent = true;
+                }
+                if data.colors.has(e) {
+                    let count = data.hit_counts[*e].count;
+                    data.colors[*e][0] = count as f32 * 0.1;
+                }
+            }
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::Deflection;
+
+    fn close(a: [f64; 2], b: [f64; 2]) -> bool {
+        (a[0] - b[0]).abs() < 1e-9 && (a[1] - b[1]).abs() < 1e-9
+    }
+
+    #[test]
+    fn centre_hit_goes_straight_back() {
+        let d = Deflection::default();
+        let v = d.deflect([-300.0, 400.0], [1.0, 0.0], 0.0, 0.0);
+        assert!(close(v, [500.0 * d.speed_up, 0.0]));
+    }
+
+    #[test]
+    fn end_hit_leaves_at_the_angle_cap() {
+        let d = Deflection::default();
+        let (cos, sin) = (d.max_angle.cos(), d.max_angle.sin());
+        let speed = 500.0 * d.speed_up;
+        // Off the right paddle's bottom end, and spin can't push it any steeper.
+        assert!(close(d.deflect([500.0, 0.0], [-1.0, 0.0], 1.0, 0.0), [-speed * cos, speed * sin]));
+        assert!(close(d.deflect([500.0, 0.0], [-1.0, 0.0], 1.0, 1000.0), [-speed * cos, speed * sin]));
+        assert!(close(d.deflect([-500.0, 0.0], [1.0, 0.0], -1.0, -1000.0), [speed * cos, -speed * sin]));
+    }
+
+    #[test]
+    fn speed_ramps_up_to_max_speed() {
+        let d = Deflection::default();
+        let mut v = [-500.0, 0.0];
+        for _ in 0..100 {
+            let n = if v[0] < 0.0 { [1.0, 0.0] } else { [-1.0, 0.0] };
+            v = d.deflect(v, n, 0.0, 0.0);
+        }
+        // An even number of hits, so back heading left.
+        assert!(close(v, [-d.max_speed, 0.0]));
+        assert!(close(d.deflect([-1190.0, 0.0], [1.0, 0.0], 0.0, 0.0), [d.max_speed, 0.0]));
+    }
+}
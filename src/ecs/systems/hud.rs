@@ -0,0 +1,101 @@
+use ecsrs::*;
+use ecsrs::system::EntityProcess;
+use std::time::Instant;
+use ecs::components::{Hud, Side};
+use ecs::scaffold::{Components, Services};
+use ecs::state::Phase;
+
+// DejaVu Sans Mono's characters are all this many times the size wide.
+const ADVANCE: f64 = 0.6;
+
+fn player(side: Side) -> &'static str {
+    match side { Side::Left => "Player 1", Side::Right => "Player 2" }
+}
+
+/// Keeps the text of every Hud entity in step with the game. The fps counter
+/// hangs left from its position, everything else is centred on it.
+pub struct HudSystem {
+    last: Option<Instant>, // When the last frame was drawn.
+    fps: Option<f64> // Smoothed, and only once there's been a frame to measure.
+}
+
+impl HudSystem {
+    pub fn new() -> HudSystem {
+        HudSystem { last: None, fps: None }
+    }
+
+    fn frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last {
+            let elapsed = now.duration_since(last);
+            let dt = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+            if dt > 0.0 {
+                self.fps = Some(match self.fps {
+                    Some(fps) => fps * 0.9 + 0.1 / dt,
+                    None => 1.0 / dt
+                });
+            }
+        }
+        self.last = Some(now);
+    }
+}
+
+impl System for HudSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+impl EntityProcess for HudSystem {
+    fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
+        use piston::input::RenderEvent;
+        let event = data.services.event.clone();
+        let event = event.borrow();
+        if event.render_args().is_none() {
+            return;
+        }
+        self.frame();
+        let score = data.services.score.clone();
+        let rally = data.services.rally;
+        let latency = data.services.latency;
+        let state = data.services.state.clone();
+        let time = data.services.time.clone();
+        for ref e in entities {
+            let hud = data.huds[*e];
+            let text = match hud {
+                Hud::Score => format!("{}   {}", score.left, score.right),
+                Hud::Rally => if rally > 0 { format!("Rally {}", rally) } else { String::new() },
+                Hud::Fps => {
+                    let mut parts = Vec::new();
+                    if let Some(fps) = self.fps {
+                        parts.push(format!("{:.0} fps", fps));
+                    }
+                    if let Some(latency) = latency {
+                        parts.push(format!("{:.0} ms", latency * 1000.0));
+                    }
+                    if time.paused {
+                        parts.push("stopped".to_string());
+                    } else if time.scale != 1.0 {
+                        parts.push(format!("x{}", time.scale));
+                    }
+                    parts.join("  ")
+                },
+                Hud::Message => if let Some(ref why) = state.stopped {
+                    why.clone()
+                } else {
+                    match state.phase {
+                        _ if state.paused => "Paused".to_string(),
+                        Phase::Title => "Press space to play".to_string(),
+                        Phase::Serve(t) => format!("{}", t.ceil()),
+                        Phase::Rally => String::new(),
+                        Phase::Scored(side, _) => format!("Point to {}", player(side)),
+                        Phase::GameOver(side) => format!("{} wins", player(side))
+                    }
+                }
+            };
+            let t = &mut data.texts[*e];
+            let width = t.size as f64 * ADVANCE * text.chars().count() as f64;
+            t.offset[0] = if hud == Hud::Fps { -width } else { -width / 2.0 };
+            t.text = text;
+        }
+    }
+}
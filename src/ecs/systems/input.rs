@@ -0,0 +1,19 @@
+use ecsrs::*;
+use ecs::scaffold::{Components, Services};
+
+/// Runs first, so every other system sees input state that includes the
+/// current event.
+pub struct InputSystem;
+
+impl System for InputSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+impl Process for InputSystem {
+    fn process(&mut self, data: &mut DataHelper<Components, Services>) {
+        let event = data.services.event.clone();
+        let event = event.borrow();
+        data.services.input.event(&event);
+    }
+}
@@ -1,15 +1,27 @@
+pub use self::ai::*;
 pub use self::collision::*;
 pub use self::control::*;
+pub use self::debug::*;
 pub use self::draw::*;
 pub use self::fade::*;
 pub use self::game::*;
+pub use self::hud::*;
+pub use self::input::*;
 pub use self::mv::*;
+pub use self::score::*;
 pub use self::shimmer::*;
+pub use self::state::*;
 
+pub mod ai;
 pub mod collision;
 pub mod control;
+pub mod debug;
 pub mod draw;
 pub mod fade;
 pub mod game;
+pub mod hud;
+pub mod input;
 pub mod mv;
+pub mod score;
 pub mod shimmer;
+pub mod state;
@@ -1,8 +1,9 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
-use ecs::debug;
+use ecsrs::system::EntityProcess;
+use ecs::broadphase::Bounds;
 use ecs::scaffold::{Components, Services};
-use piston::input::{Event, ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
+use ecs::components::{Edge, ExitEvent, Side};
+use piston::input::UpdateEvent;
 
 pub struct MoveSystem;
 
@@ -11,91 +12,149 @@ impl System for MoveSystem {
     type Services = Services;
 }
 
+// Lines hold their end points in world coordinates, so they have to be moved
+// along with the position.
+fn shift(data: &mut DataHelper<Components, Services>, e: &EntityData<Components>, dx: f64, dy: f64) {
+    use ecs::components::ShapeVariant::Line;
+    {
+        let position = &mut(data.positions[*e]);
+        position.x += dx;
+        position.y += dy;
+    }
+    if let Line(ref mut l) = data.shapes[*e].variant {
+        l[0] += dx;
+        l[1] += dy;
+        l[2] += dx;
+        l[3] += dy;
+    }
+}
+
 impl EntityProcess for MoveSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
-        use graphics::*;
-        use ecs::components::ShapeVariant as shape;
         use ecs::components::ClampVariant::*;
+        let event = data.services.event.clone();
+        let event =  event.borrow();
+        if event.update_args().is_some() {
+            data.services.exits.clear();
+            if !data.services.state.running() {
+                // Hold still rather than drawing between where things were
+                // and where they stopped.
+                for ref e in entities {
+                    if data.previous_positions.has(e) {
+                        let last = data.positions[*e].clone();
+                        data.previous_positions[*e] = last;
+                    }
+                }
+                return;
+            }
+        }
+        let arena = data.services.arena.clone();
+        let fudge = arena.disp_fudge;
         for ref e in entities {
             let (vx, vy) = {
                 let v = &data.velocities[*e];
                 (v.x, v.y)
             };
-            let shape = data.shapes[*e].clone();
             let clamp = data.clamps[*e].clone();
-            let event = data.services.event.clone();
-            let event =  event.borrow();
                 if let Some(update) = event.update_args() {
                     let dt = update.dt;
-                    let view_width = ::WINDOW_W - 2.0 * ::WINDOW_PADDING;
-                    let view_height = ::WINDOW_H - 2.0 * ::WINDOW_PADDING;
+                    let view_width = arena.view_w();
+                    let view_height = arena.view_h();
 
-                    let (px, py) = {
-                        let position = &mut(data.positions[*e]);
-                        position.x += vx * dt;
-                        position.y += vy * dt;
-                        (position.x, position.y)
-                    };
+                    if data.previous_positions.has(e) {
+                        let last = data.positions[*e].clone();
+                        data.previous_positions[*e] = last;
+                    }
+                    shift(data, e, vx * dt, vy * dt);
 
-                    let (w, h) = match shape.variant {
-                        shape::Circle(r) => (r, r),
-                        shape::Square(w,h) => (w, h),
-                        shape::Point => (1.0, 0.0),
-                        shape::Line(_) => (0.0, 0.0)
-                    };
+                    let bounds = Bounds::of(&data.positions[*e], &data.shapes[*e].variant);
+                    let (left, top) = (bounds.min[0], bounds.min[1]);
+                    let (right, bottom) = (bounds.max[0], bounds.max[1]);
 
                     let velocity_mult = match clamp.variant {
-                      Bounce => -1.0,
+                      Bounce | Goal => -1.0,
                       Stop => 0.0,
                       _ => 1.0
                     };
 
                     match clamp.variant {
                       Bounce | Stop => {
-                        if px + w > view_width {
-                          {
-                              let position = &mut(data.positions[*e]);
-                              position.x = view_width - w - ::DISP_FUDGE;
-                          }
+                        if right > view_width {
+                          shift(data, e, view_width - right - fudge, 0.0);
                           let velocity  = &mut(data.velocities[*e]);
                           velocity.x *= velocity_mult;
-                        } else if px - w < 0.0 {
-                          {
-                              let position = &mut(data.positions[*e]);
-                              position.x = w + ::DISP_FUDGE;
-                          }
+                        } else if left < 0.0 {
+                          shift(data, e, -left + fudge, 0.0);
                           let velocity  = &mut(data.velocities[*e]);
                           velocity.x *= velocity_mult;
                         }
-                        if py + h > view_height {
-                          {
-                              let position = &mut(data.positions[*e]);
-                              position.y = view_height - h - ::DISP_FUDGE;
-                          }
+                        if bottom > view_height {
+                          shift(data, e, 0.0, view_height - bottom - fudge);
                           let velocity  = &mut(data.velocities[*e]);
                           velocity.y *= velocity_mult;
-                        } else if py - h < 0.0 {
-                          {
-                              let position = &mut(data.positions[*e]);
-                              position.y = h + ::DISP_FUDGE;
-                          }
+                        } else if top < 0.0 {
+                          shift(data, e, 0.0, -top + fudge);
                           let velocity  = &mut(data.velocities[*e]);
                           velocity.y *= velocity_mult;
                         }
                       },
                       Remove => {
-                        if px - w > view_width
-                        || px + w < 0.0 {
-                            println!("Should remove, went off horizontal edge");
+                        // Only once it is entirely out of sight.
+                        let edge = if left > view_width {
+                            Some(Edge::Right)
+                        } else if right < 0.0 {
+                            Some(Edge::Left)
+                        } else if top > view_height {
+                            Some(Edge::Bottom)
+                        } else if bottom < 0.0 {
+                            Some(Edge::Top)
+                        } else {
+                            None
+                        };
+                        if let Some(edge) = edge {
+                            data.services.exits.push(ExitEvent { entity: ***e, edge: edge });
+                            data.remove_entity(***e);
+                            continue;
+                        }
+                      },
+                      Goal => {
+                        if bottom > view_height {
+                          shift(data, e, 0.0, view_height - bottom - fudge);
+                          let velocity  = &mut(data.velocities[*e]);
+                          velocity.y *= velocity_mult;
+                        } else if top < 0.0 {
+                          shift(data, e, 0.0, -top + fudge);
+                          let velocity  = &mut(data.velocities[*e]);
+                          velocity.y *= velocity_mult;
+                        }
+                        let y = data.positions[*e].y;
+                        let scored = if arena.in_goal(arena.right, y) && left > view_width {
+                            Some((Side::Left, Edge::Right))
+                        } else if arena.in_goal(arena.left, y) && right < 0.0 {
+                            Some((Side::Right, Edge::Left))
+                        } else {
+                            None
+                        };
+                        // Off a side wall, or the post either side of a goal.
+                        if scored.is_none() {
+                          if right > view_width && !arena.in_goal(arena.right, y) {
+                            shift(data, e, view_width - right - fudge, 0.0);
+                            let velocity  = &mut(data.velocities[*e]);
+                            velocity.x = -velocity.x.abs();
+                          } else if left < 0.0 && !arena.in_goal(arena.left, y) {
+                            shift(data, e, -left + fudge, 0.0);
+                            let velocity  = &mut(data.velocities[*e]);
+                            velocity.x = velocity.x.abs();
+                          }
                         }
-                        if py - h > view_height
-                        || py + h < 0.0 {
-                            println!("Should remove, went off vertical edge");
+                        if let Some((side, edge)) = scored {
+                            data.services.score.goals.push(side);
+                            data.services.exits.push(ExitEvent { entity: ***e, edge: edge });
+                            data.remove_entity(***e);
+                            continue;
                         }
                       }
                 }
-                let (v, pos)  = (&data.velocities[*e].clone(), &data.positions[*e].clone());
-                debug::line(data, [pos.x, pos.y, pos.x + v.x * dt * 10.0, pos.y + v.y * dt * 10.0], 1.0);
             }
         }
     }
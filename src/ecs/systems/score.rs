@@ -0,0 +1,25 @@
+use ecsrs::*;
+use ecs::scaffold::{Components, Services};
+use ecs::components::Side;
+
+pub struct ScoreSystem;
+
+impl System for ScoreSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+impl Process for ScoreSystem {
+    fn process(&mut self, data: &mut DataHelper<Components, Services>) {
+        let goals: Vec<Side> = data.services.score.goals.drain(..).collect();
+        for side in goals {
+            data.services.score.award(side);
+            data.services.rally = 0;
+            let winner = data.services.score.winner;
+            data.services.state.scored(side, winner);
+            if winner.is_some() {
+                break;
+            }
+        }
+    }
+}
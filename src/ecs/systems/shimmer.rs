@@ -1,9 +1,9 @@
 use ecsrs::*;
-use ecsrs::system::{EntityProcess, EntitySystem};
-use ecs::scaffold::{Components, Services};
-use piston::input::{Event, ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
+use ecsrs::system::EntityProcess;
+use ecs::scaffold::{in_order, Components, Services};
+use piston::input::UpdateEvent;
 
-use rand::{self, Rng};
+use rand::Rng;
 
 pub struct ShimmerSystem;
 
@@ -16,13 +16,18 @@ impl EntityProcess for ShimmerSystem {
     fn process(&mut self, entities: EntityIter<Components>, data: &mut DataHelper<Components, Services>) {
         let event = data.services.event.clone();
         let event =  event.borrow();
-        if let Some(update) = event.render_args() {
-            for ref e in entities {
+        // On updates rather than frames, so it draws the same numbers from
+        // the rng however fast the game is drawn.
+        if event.update_args().is_some() {
+            for ref e in in_order(entities) {
+                let shade = {
+                    let ref mut rng = data.services.rng;
+                    [rng.gen_range(0.3, 1.0), rng.gen_range(0.3, 1.0), rng.gen_range(0.3, 1.0)]
+                };
                 let color = &mut data.colors[*e];
-                let ref mut rng = rand::thread_rng();
-                color[0] = rng.gen_range(0.3, 1.0);
-                color[1] = rng.gen_range(0.3, 1.0);
-                color[2] = rng.gen_range(0.3, 1.0);
+                color[0] = shade[0];
+                color[1] = shade[1];
+                color[2] = shade[2];
             }
         }
     }
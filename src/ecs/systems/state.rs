@@ -0,0 +1,100 @@
+use ecsrs::*;
+use piston::input::{Button, Event, Input, PressEvent, UpdateEvent};
+use piston::input::keyboard::Key;
+use ecs::components::Score;
+use ecs::scaffold::{Components, Services};
+use ecs::state::{Phase, SERVE_TIME};
+
+/// Moves the game between phases: keys start, pause and restart it, and
+/// updates count down to each serve.
+pub struct StateSystem;
+
+fn new_game(services: &mut Services) {
+    services.score = Score::new(services.score.win.clone());
+    services.rally = 0;
+    services.state.phase = Phase::Serve(SERVE_TIME);
+}
+
+impl System for StateSystem {
+    type Components = Components;
+    type Services = Services;
+}
+
+impl Process for StateSystem {
+    fn process(&mut self, data: &mut DataHelper<Components, Services>) {
+        let event = data.services.event.clone();
+        let event = event.borrow();
+        if let Some(update) = event.update_args() {
+            // A network game restarts on the tick either player's input asks
+            // to, which both ends run with the same inputs.
+            let restart = data.services.net_input.map_or(false, |inputs| inputs[0].restart || inputs[1].restart);
+            if let Phase::GameOver(_) = data.services.state.phase {
+                if restart && data.services.state.stopped.is_none() {
+                    new_game(&mut data.services);
+                }
+            }
+            if data.services.state.tick(update.dt) {
+                ::make_ball(data);
+            }
+            return;
+        }
+        if let Event::Input(Input::Focus(false)) = *event {
+            if data.services.state.can_pause() {
+                data.services.state.paused = true;
+            }
+            return;
+        }
+        if data.services.state.stopped.is_some() {
+            return;
+        }
+        let (phase, lockstep) = (data.services.state.phase, data.services.state.lockstep);
+        match event.press_args() {
+            Some(Button::Keyboard(Key::P)) => if data.services.state.paused {
+                data.services.state.paused = false;
+            } else if data.services.state.can_pause() {
+                data.services.state.paused = true;
+            },
+            Some(Button::Keyboard(Key::Space)) | Some(Button::Keyboard(Key::Return)) => match phase {
+                Phase::Title => data.services.state.phase = Phase::Serve(SERVE_TIME),
+                Phase::GameOver(_) if !lockstep => new_game(&mut data.services),
+                _ => ()
+            },
+            _ => ()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use piston::input::{Button, Event, Input};
+    use piston::input::keyboard::Key;
+    use ecs::components::Side;
+    use ecs::input::PaddleInput;
+    use ecs::state::Phase;
+    use headless::{self, Headless};
+    use Options;
+
+    #[test]
+    fn network_games_restart_through_the_exchanged_input() {
+        let mut sim = Headless::new(headless::DEFAULT_DT, &Options::default());
+        {
+            let services = &mut sim.world.data.services;
+            services.state.lockstep = true;
+            services.state.phase = Phase::GameOver(Side::Left);
+            services.score.left = 11;
+            services.net_input = Some([PaddleInput::default(); 2]);
+        }
+        // Only this end would see the key.
+        *(sim.world.data.services.event.borrow_mut()) = Event::Input(Input::Press(Button::Keyboard(Key::Space)));
+        sim.world.update();
+        sim.step();
+        assert_eq!(sim.world.data.services.state.phase, Phase::GameOver(Side::Left));
+
+        let restart = PaddleInput { restart: true, ..PaddleInput::default() };
+        sim.world.data.services.net_input = Some([PaddleInput::default(), restart]);
+        sim.step();
+        let services = &sim.world.data.services;
+        assert!(match services.state.phase { Phase::Serve(_) => true, _ => false });
+        assert_eq!((services.score.left, services.score.winner), (0, None));
+    }
+}
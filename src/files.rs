@@ -0,0 +1,158 @@
+// Reading the game's data files, with errors that say which file and where.
+
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{Json, JsonEvent, Object, Parser, StackElement};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::vec;
+use toml;
+
+pub fn read_to_string(path: &Path) -> Result<String, String> {
+    let mut src = String::new();
+    try!(File::open(path)
+         .and_then(|mut f| f.read_to_string(&mut src))
+         .map_err(|e| format!("{}: {}", path.display(), e)));
+    Ok(src)
+}
+
+/// Reads `path` and hands it to `parse`, putting the path in front of any
+/// error.
+pub fn load<T, F>(path: &Path, parse: F) -> Result<T, String>
+    where F: FnOnce(&str) -> Result<T, String> {
+    let src = try!(read_to_string(path));
+    parse(&src).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Decodes a whole TOML document. Syntax errors are given as `line:column`.
+pub fn decode_toml<T: Decodable>(src: &str) -> Result<T, String> {
+    let mut parser = toml::Parser::new(src);
+    let table = match parser.parse() {
+        Some(table) => table,
+        None => {
+            let errors: Vec<String> = parser.errors.iter().map(|e| {
+                let (line, col) = parser.to_linecol(e.lo);
+                format!("{}:{}: {}", line + 1, col + 1, e.desc)
+            }).collect();
+            return Err(errors.join("\n"));
+        }
+    };
+    T::decode(&mut toml::Decoder::new(toml::Value::Table(table))).map_err(|e| format!("{}", e))
+}
+
+/// Parses a JSON document as `Json::from_str` does, except that numbers with
+/// a fraction or exponent are read from their text with `str::parse`.
+/// rustc_serialize's own reading is off in the last bit or two, and saved
+/// games have to come back exactly as they were written.
+pub fn parse_json(src: &str) -> Result<Json, String> {
+    let mut floats = float_tokens(src).into_iter();
+    let mut parser = Parser::new(src.chars());
+    let first = parser.next();
+    let json = try!(build_json(&mut parser, first, &mut floats));
+    match parser.next() {
+        None => Ok(json),
+        Some(JsonEvent::Error(e)) => Err(format!("{}", e)),
+        Some(_) => Err("Trailing characters after the document".to_string())
+    }
+}
+
+fn build_json<I>(parser: &mut Parser<I>, event: Option<JsonEvent>, floats: &mut vec::IntoIter<&str>)
+                 -> Result<Json, String> where I: Iterator<Item = char> {
+    match event {
+        Some(JsonEvent::ObjectStart) => {
+            let mut o = Object::new();
+            loop {
+                let event = parser.next();
+                match event {
+                    Some(JsonEvent::ObjectEnd) => return Ok(Json::Object(o)),
+                    Some(JsonEvent::Error(e)) => return Err(format!("{}", e)),
+                    _ => ()
+                }
+                let key = match parser.stack().top() {
+                    Some(StackElement::Key(key)) => key.to_string(),
+                    _ => return Err("Expected a key".to_string())
+                };
+                let value = try!(build_json(parser, event, floats));
+                o.insert(key, value);
+            }
+        }
+        Some(JsonEvent::ArrayStart) => {
+            let mut a = Vec::new();
+            loop {
+                match parser.next() {
+                    Some(JsonEvent::ArrayEnd) => return Ok(Json::Array(a)),
+                    event => a.push(try!(build_json(parser, event, floats)))
+                }
+            }
+        }
+        Some(JsonEvent::F64Value(v)) => {
+            Ok(Json::F64(floats.next().and_then(|t| t.parse().ok()).unwrap_or(v)))
+        }
+        Some(JsonEvent::I64Value(v)) => Ok(Json::I64(v)),
+        Some(JsonEvent::U64Value(v)) => Ok(Json::U64(v)),
+        Some(JsonEvent::BooleanValue(v)) => Ok(Json::Boolean(v)),
+        Some(JsonEvent::StringValue(v)) => Ok(Json::String(v)),
+        Some(JsonEvent::NullValue) => Ok(Json::Null),
+        Some(JsonEvent::Error(e)) => Err(format!("{}", e)),
+        Some(JsonEvent::ObjectEnd) | Some(JsonEvent::ArrayEnd) | None => {
+            Err("Unexpected end of the document".to_string())
+        }
+    }
+}
+
+// The text of each number in `src` with a fraction or exponent, in order.
+fn float_tokens(src: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    let mut start = None;
+    for (i, c) in src.char_indices().chain(Some((src.len(), ' '))) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match start {
+            Some(s) if !(c.is_digit(10) || "+-.eE".contains(c)) => {
+                let token = &src[s..i];
+                if token.contains(|c| c == '.' || c == 'e' || c == 'E') {
+                    tokens.push(token);
+                }
+                start = None;
+            }
+            None if c.is_digit(10) || c == '-' => start = Some(i),
+            _ => ()
+        }
+        in_string = c == '"';
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_come_back_exactly() {
+        let values = [377.773637955543, 0.02500000000000001, -194.69290520134174, 1e-300];
+        let json = Json::Array(values.iter().map(|v| Json::F64(*v)).collect());
+        assert_eq!(parse_json(&format!("{}", json)), Ok(json));
+    }
+
+    #[test]
+    fn reads_what_from_str_reads() {
+        let src = r#"{"a": [1, -2, 2.5e3, true, null], "b\\\"1.5": {"c": "x\\\"y 2.5"}, "d": false}"#;
+        assert_eq!(parse_json(src), Json::from_str(src).map_err(|e| format!("{}", e)));
+    }
+
+    #[test]
+    fn rejects_what_from_str_rejects() {
+        for src in &["", "{", "[1,]", "{\"a\" 1}", "[1] 2", "1.5.5"] {
+            assert!(parse_json(src).is_err(), "accepted {}", src);
+        }
+    }
+}
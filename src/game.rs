@@ -1,143 +1,112 @@
-#![allow(unused_variables, unused_imports)]
+extern crate rustecspong;
 
-#[macro_use]
-extern crate ecs as ecsrs;
+use rustecspong::Options;
+use rustecspong::ecs::arena::Arena;
+use rustecspong::ecs::bindings::Bindings;
+use rustecspong::ecs::components::WinCondition;
+use rustecspong::ecs::debug::Toggles;
+use rustecspong::ecs::prefab::Prefabs;
+use rustecspong::ecs::snapshot::Snapshot;
+use rustecspong::headless::{self, Headless};
+use rustecspong::replay::{Recording, Replay};
 
-extern crate graphics;
-extern crate opengl_graphics;
-extern crate piston;
-//extern crate piston_window;
-extern crate rand;
-extern crate sdl2_window;
-extern crate shader_version;
-extern crate vecmath;
-
-mod ecs;
-
-use ecsrs::*;
-//use piston::input::{Event, ReleaseEvent, UpdateEvent, PressEvent, RenderEvent, RenderArgs, UpdateArgs};
-//use piston::input::Button::Keyboard;
-//use piston::input::keyboard;
-use piston::input::*;
-use piston::event_loop::*;
-use rand::Rng;
-use std::cell::Cell;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
-use piston::window::{WindowSettings};
-//use piston_window::{PistonWindow};
-
-use opengl_graphics::{OpenGL, GlGraphics};
-use sdl2_window::Sdl2Window as Window;
-use self::ecs::components::*;
-use self::ecs::scaffold::{Systems, Components};
-
-const WINDOW_W: f64 = 800.0;
-const WINDOW_H: f64 = 600.0;
-const WINDOW_PADDING: f64 = 40.0;
-const VIEW_W: f64 = (WINDOW_W - 2.0 * WINDOW_PADDING);
-const VIEW_H: f64 = (WINDOW_H - 2.0 * WINDOW_PADDING);
-const DISP_FUDGE: f64 = 5.0;
-
-fn make_ball(world: &mut World<Systems>) {
-    const BALL_R: f64 = 10.0;
-    let ref mut rng = rand::thread_rng();
-    let xoff = rng.gen_range(-100.0, 100.0);
-    let yoff = rng.gen_range(-100.0, 100.0);
-
-    let vx = rng.gen_range(400.0, 500.0);
-    let vy = rng.gen_range(400.0, 500.0);
-
-    let x = VIEW_W / 2.0 + xoff;
-    let y = VIEW_H / 2.0 + yoff;
-
-    let entity = world.create_entity(|entity: BuildData<Components>, data: &mut Components| {
-        data.positions.add(&entity,
-            Position{
-                x: x,
-                y: y
-        });
-        data.velocities.add(&entity,
-            Velocity{
-                x: vx,
-                y: vy,
-        });
-        data.shimmers.add(&entity, Shimmer);
-        data.shapes.add(&entity,
-            Shape {
-                variant: ShapeVariant::Circle(BALL_R),
-                border: None
-        });
-        data.colors.add(&entity, [1.0, 0.5, 0.2, 1.0]);
-        data.clamps.add(&entity,
-            WindowClamp {
-               variant: ClampVariant::Bounce
-        });
-    });
-}
-
-fn make_player(world: &mut World<Systems>, p1: bool) {
-    const FROM_WALL: f64 = 20.0;
-    const PADDLE_W: f64 = 10.0;
-    const PADDLE_H: f64 = 60.0;
-    let x = if p1 {
-        FROM_WALL
-    } else {
-        VIEW_W - FROM_WALL
+fn main() {
+    const DEFAULT_TICKS: u64 = 60 * 60 * 10;
+    let args: Vec<String> = std::env::args().collect();
+    let mut options = Options {
+        seed: arg_value(&args, "--seed"),
+        arena: load(&args, "--arena", Arena::load).unwrap_or(Arena::default()),
+        prefabs: load(&args, "--prefabs", Prefabs::load).unwrap_or(Prefabs::default()),
+        left_ai: arg_value(&args, "--left-ai"),
+        right_ai: arg_value(&args, "--ai"),
+        snapshot: load(&args, "--load", Snapshot::load),
+        record: arg_value(&args, "--record"),
+        replay: load(&args, "--replay", Recording::load),
+        host: arg_value(&args, "--host"),
+        join: arg_value(&args, "--join"),
+        title: false,
+        debug: arg_value(&args, "--debug").unwrap_or(Toggles::default())
+    };
+    let first_to = arg_value(&args, "--first-to").unwrap_or(options.arena.win.first_to);
+    let win_by = arg_value(&args, "--win-by").unwrap_or(options.arena.win.win_by);
+    options.arena.win = match WinCondition::new(first_to, win_by) {
+        Ok(win) => win,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
     };
-    let y = VIEW_H / 2.0;
-    let entity = world.create_entity(|entity: BuildData<Components>, data: &mut Components| {
-        data.positions.add(&entity,
-            Position{
-                x: x,
-                y: y
-        });
-        data.velocities.add(&entity,
-            Velocity{
-                x: 0.0,
-                y: 0.0,
-        });
-        data.shapes.add(&entity,
-            Shape {
-                variant: ShapeVariant::Square(PADDLE_W, PADDLE_H),
-                border: None
-        });
-        data.colors.add(&entity, [0.3, 0.4, 1.0, 1.0]);
-        data.player_controllers.add(&entity,
-            PlayerController {
-                up: if p1 { keyboard::Key::W } else { keyboard::Key::I },
-                down: if p1 { keyboard::Key::S } else { keyboard::Key::K },
-        });
-        data.clamps.add(&entity,
-            WindowClamp {
-               variant: ClampVariant::Stop
-        });
-        data.hit_counts.add(&entity, HitCount { recent: false, count: 0 });
-    });
+    if let Some(bindings) = load(&args, "--bindings", Bindings::load) {
+        bindings.apply(&mut options.prefabs);
+    }
+    let no_window = args.iter().any(|a| a == "--headless");
+    let verify = args.iter().any(|a| a == "--verify");
+    if let (Some(recording), true) = (options.replay.clone(), no_window || verify) {
+        let mut replay = Replay::new(recording);
+        let world = replay.run(verify);
+        let score = &world.data.services.score;
+        println!("Replayed {} of {} ticks: {} - {}",
+                 replay.tick, replay.recording.ticks.len(), score.left, score.right);
+        if let Some(m) = replay.mismatch {
+            println!("World differs from the recording at tick {}: expected {:016x}, got {:016x}",
+                     m.tick, m.expected, m.actual);
+            if verify {
+                std::process::exit(1);
+            }
+        }
+    } else if no_window {
+        let ticks = arg_value(&args, "--ticks").unwrap_or(DEFAULT_TICKS);
+        let mut sim = Headless::new(headless::DEFAULT_DT, &options);
+        let ran = sim.run(ticks);
+        {
+            let services = &sim.world.data.services;
+            let score = &services.score;
+            println!("Seed {} ran {} ticks: {} - {} winner: {:?}",
+                     services.seed, ran, score.left, score.right, score.winner);
+        }
+        if let Some(path) = arg_value::<String>(&args, "--screenshot") {
+            if let Err(e) = sim.screenshot().save(&path) {
+                println!("Couldn't save screenshot {}", e);
+            }
+        }
+        if let (Some(recording), Some(path)) = (sim.recording.as_ref(), options.record.as_ref()) {
+            if let Err(e) = recording.save(path) {
+                println!("Couldn't save recording {}", e);
+            }
+        }
+    } else {
+        rustecspong::run(Options { title: true, ..options });
+    }
 }
 
-//#[no_mangle]
-fn main() {
-    let opengl = OpenGL::V3_2;
-    let window: Window = WindowSettings::new(
-      "Pong".to_string(),
-      piston::window::Size {
-          width: WINDOW_W as u32,
-          height: WINDOW_H as u32
-      }
-    ).fullscreen(true).exit_on_esc(true).samples(4).build().unwrap();
-    let mut gl = GlGraphics::new(opengl);
-
-    let mut world = World::<Systems>::new();
-    world.systems.draw.gl = Some(RefCell::new(gl));
-    make_ball(&mut world);
-    make_player(&mut world, true);
-    make_player(&mut world, false);
+// The value given after `flag`, if it's there, exiting if it doesn't parse.
+fn arg_value<T>(args: &[String], flag: &str) -> Option<T>
+    where T: std::str::FromStr, T::Err: std::fmt::Display {
+    args.iter().position(|a| a == flag).map(|i| {
+        match args.get(i + 1).map(|v| v.parse()) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                println!("Bad value for {}: {}", flag, e);
+                std::process::exit(1);
+            }
+            None => {
+                println!("{} needs a value", flag);
+                std::process::exit(1);
+            }
+        }
+    })
+}
 
-    for e in window.events() {
-        use piston::input::{ ReleaseEvent, UpdateEvent, PressEvent, RenderEvent};
-        *(world.data.services.event.borrow_mut()) = e;
-        world.update();
-    }
+// Loads the file named after `flag`, if given, exiting on any error.
+fn load<T, F>(args: &[String], flag: &str, loader: F) -> Option<T>
+    where F: Fn(String) -> Result<T, String> {
+    arg_value::<String>(args, flag).map(|path| {
+        match loader(path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                println!("Couldn't load {}", e);
+                std::process::exit(1);
+            }
+        }
+    })
 }
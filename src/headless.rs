@@ -0,0 +1,98 @@
+use ecsrs::*;
+use piston::input::{Event, RenderArgs, UpdateArgs};
+use ecs::components::Difficulty;
+use ecs::raster::Raster;
+use ecs::render::Target;
+use ecs::scaffold::Systems;
+use replay::Recording;
+use timestep::FixedStep;
+use Options;
+
+pub const DEFAULT_DT: f64 = ::timestep::FIXED_DT;
+
+/// Steps the world with synthetic update events and no window or GL context.
+pub struct Headless {
+    pub world: World<Systems>,
+    pub dt: f64,
+    pub ticks: u64,
+    pub recording: Option<Recording>, // When `options.record` is set.
+    clock: FixedStep
+}
+
+impl Headless {
+    /// Paddles without an AI in `options` get a hard one, since nobody is at
+    /// the keyboard.
+    pub fn new(dt: f64, options: &Options) -> Headless {
+        let debug = options.debug;
+        let mut options = options.clone();
+        options.left_ai = options.left_ai.or(Some(Difficulty::Hard));
+        options.right_ai = options.right_ai.or(Some(Difficulty::Hard));
+        let recording = options.record.as_ref().map(|_| Recording::new(dt, &options));
+        if let Some(ref recording) = recording {
+            options = recording.options();
+        }
+        let mut world = World::<Systems>::new();
+        ::setup(&mut world, &options);
+        world.data.services.debug.show = debug;
+        Headless {
+            world: world,
+            dt: dt,
+            ticks: 0,
+            recording: recording,
+            clock: FixedStep::new(dt)
+        }
+    }
+
+    pub fn step(&mut self) {
+        *(self.world.data.services.event.borrow_mut()) = Event::Update(UpdateArgs { dt: self.dt });
+        self.world.update();
+        if let Some(ref mut recording) = self.recording {
+            recording.tick(&mut self.world);
+        }
+        self.ticks += 1;
+    }
+
+    /// Runs up to `ticks` updates, stopping early once a side has won.
+    /// Returns the number of ticks actually run.
+    pub fn run(&mut self, ticks: u64) -> u64 {
+        let start = self.ticks;
+        while self.ticks - start < ticks {
+            if self.world.data.services.score.winner.is_some() {
+                break;
+            }
+            self.step();
+        }
+        self.ticks - start
+    }
+
+    /// Runs the ticks due after `elapsed` seconds of real time, as the window
+    /// would, so `world.data.services.time` can pause, step or scale them.
+    /// Returns the number of ticks run.
+    pub fn advance(&mut self, elapsed: f64) -> u32 {
+        let steps = self.world.data.services.time.advance(&mut self.clock, elapsed);
+        for _ in 0..steps {
+            self.step();
+        }
+        self.world.data.services.alpha = self.clock.alpha();
+        steps
+    }
+
+    /// Draws the world as it is after the last step into an image the size
+    /// of the window.
+    pub fn screenshot(&mut self) -> Raster {
+        let (w, h) = {
+            let arena = &self.world.data.services.arena;
+            (arena.window_w as u32, arena.window_h as u32)
+        };
+        self.world.systems.draw.target = Some(Target::Raster(Raster::new(w, h)));
+        self.world.data.services.alpha = 1.0;
+        *(self.world.data.services.event.borrow_mut()) = Event::Render(RenderArgs {
+            ext_dt: 0.0, width: w, height: h, draw_width: w, draw_height: h
+        });
+        self.world.update();
+        match self.world.systems.draw.target.take() {
+            Some(Target::Raster(raster)) => raster,
+            _ => unreachable!()
+        }
+    }
+}
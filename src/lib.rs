@@ -0,0 +1,277 @@
+#[macro_use]
+extern crate ecs as ecsrs;
+
+extern crate graphics;
+extern crate opengl_graphics;
+extern crate piston;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate sdl2_window;
+extern crate shader_version;
+extern crate toml;
+extern crate vecmath;
+
+pub mod ecs;
+pub mod files;
+pub mod headless;
+pub mod net;
+pub mod png;
+pub mod replay;
+pub mod timestep;
+
+use ecsrs::*;
+use piston::input::*;
+use piston::event_loop::*;
+use piston::window::WindowSettings;
+
+use opengl_graphics::{OpenGL, GlGraphics};
+use sdl2_window::Sdl2Window as Window;
+use self::ecs::arena::Arena;
+use self::ecs::debug::Toggles;
+use self::ecs::components::*;
+use self::ecs::prefab::Prefabs;
+use self::ecs::render::{Gl, Target};
+use self::ecs::snapshot::Snapshot;
+use self::ecs::state::{Phase, SERVE_TIME};
+use self::ecs::input::PaddleInput;
+use self::net::Session;
+use self::replay::{Recording, Replay};
+use self::ecs::scaffold::{Systems, Components, Services};
+
+// Where F5 saves the game, load it again with --load.
+const SNAPSHOT_FILE: &'static str = "snapshot.json";
+
+pub fn make_ball(data: &mut DataHelper<Components, Services>) {
+    let prefab = data.services.prefabs.ball.clone();
+    prefab.spawn(data);
+}
+
+// `ai` swaps whatever controller the prefab has for a computer player.
+pub fn make_player(world: &mut World<Systems>, p1: bool, ai: Option<Difficulty>) {
+    let mut prefab = if p1 {
+        world.data.services.prefabs.left_paddle.clone()
+    } else {
+        world.data.services.prefabs.right_paddle.clone()
+    };
+    if let Some(difficulty) = ai {
+        prefab.player_controller = None;
+        prefab.ai_controller = Some(AiController::new(difficulty));
+    }
+    prefab.spawn(&mut world.data);
+}
+
+// Text kept up to date by HudSystem. It isn't part of the game, so snapshots
+// leave it out.
+pub fn make_hud(data: &mut DataHelper<Components, Services>) {
+    let (w, h) = (data.services.arena.view_w(), data.services.arena.view_h());
+    let huds = [
+        (Hud::Score, [w / 2.0, 48.0], 32),
+        (Hud::Rally, [w / 2.0, 72.0], 14),
+        (Hud::Fps, [w - 8.0, 24.0], 14),
+        (Hud::Message, [w / 2.0, h / 2.0 - 40.0], 32)
+    ];
+    for &(hud, at, size) in huds.iter() {
+        data.create_entity(|entity: BuildData<Components>, c: &mut Components| {
+            c.positions.add(&entity, Position { x: at[0], y: at[1] });
+            c.texts.add(&entity, Text { text: String::new(), size: size, color: [1.0, 1.0, 1.0, 0.8], offset: [0.0, 0.0] });
+            c.huds.add(&entity, hud);
+        });
+    }
+}
+
+/// Everything that can be chosen about a game before it starts.
+#[derive(Clone, Default)]
+pub struct Options {
+    pub seed: Option<u64>,
+    pub arena: Arena,
+    pub prefabs: Prefabs,
+    pub left_ai: Option<Difficulty>, // Computer controls the paddle, otherwise the keyboard.
+    pub right_ai: Option<Difficulty>,
+    pub snapshot: Option<Snapshot>, // Start from here instead of a fresh serve.
+    pub record: Option<String>, // Save every tick's input here on exit.
+    pub replay: Option<Recording>, // Play this back and ignore the keyboard.
+    pub host: Option<u16>, // Wait for another player to join on this port.
+    pub join: Option<String>, // Address of a host to play against.
+    pub title: bool, // Wait on the title screen for a key before the first serve.
+    pub debug: Toggles // Debug overlay shown from the start.
+}
+
+/// Fills a new world with the ball and paddles described by `options`.
+pub fn setup(world: &mut World<Systems>, options: &Options) {
+    if let Some(seed) = options.seed {
+        world.data.services.reseed(seed);
+    }
+    world.data.services.arena = options.arena.clone();
+    world.data.services.prefabs = options.prefabs.clone();
+    make_hud(&mut world.data);
+    match options.snapshot {
+        Some(ref snapshot) => snapshot.restore(world),
+        None => {
+            // The ball comes when the countdown to the first serve ends.
+            world.data.services.score = Score::new(options.arena.win.clone());
+            world.data.services.state.phase = if options.title { Phase::Title } else { Phase::Serve(SERVE_TIME) };
+            make_player(world, true, options.left_ai);
+            make_player(world, false, options.right_ai);
+        }
+    }
+}
+
+#[no_mangle]
+pub extern fn pong_main() {
+    run(Options { title: true, ..Options::default() });
+}
+
+pub fn run(options: Options) {
+    let debug = options.debug; // Not part of a recording or a network game.
+    // Both ends of a network game start from the host's snapshot, and each
+    // steers its own paddle with its own bindings.
+    let mut net = None;
+    if let Some(port) = options.host {
+        let mut hosted = options.clone();
+        hosted.left_ai = None;
+        hosted.right_ai = None;
+        hosted.title = false; // Nobody would see the guest press a key.
+        let start = Recording::new(timestep::FIXED_DT, &hosted);
+        println!("Waiting for a player on port {}", port);
+        match Session::host(port, &start, net::DEFAULT_DELAY) {
+            Ok(session) => {
+                println!("{} joined", session.peer);
+                net = Some((session, start.options()));
+            }
+            Err(e) => return println!("Couldn't host {}", e)
+        }
+    } else if let Some(ref addr) = options.join {
+        println!("Joining {}", addr);
+        match Session::join(addr) {
+            Ok((session, start)) => net = Some((session, start.options())),
+            Err(e) => return println!("Couldn't join {}", e)
+        }
+    }
+    let local_controller = match net {
+        Some((ref session, _)) if session.side == Side::Right => options.prefabs.right_paddle.player_controller.clone(),
+        _ => options.prefabs.left_paddle.player_controller.clone()
+    }.unwrap_or(PlayerController { up: vec![], down: vec![], axis: None, accel: None, decel: None });
+
+    // Recording and replaying are for games on one machine.
+    let local = net.is_none();
+    let record = if local { options.record.clone() } else { None };
+    let mut replay = if local { options.replay.clone().map(Replay::new) } else { None };
+    let mut recording = record.as_ref().map(|_| Recording::new(timestep::FIXED_DT, &options));
+    let options = match (&net, &replay, &recording) {
+        (&Some((_, ref start)), _, _) => start.clone(),
+        (_, &Some(ref replay), _) => replay.recording.options(),
+        (_, _, &Some(ref recording)) => recording.options(),
+        _ => options.clone()
+    };
+    let mut net = net.map(|(session, _)| session);
+
+    let mut world = World::<Systems>::new();
+    setup(&mut world, &options);
+    world.data.services.state.lockstep = !local;
+    world.data.services.debug.show = debug;
+    println!("Seed: {}", world.data.services.seed);
+
+    let arena = world.data.services.arena.clone(); // A snapshot brings its own.
+    let opengl = OpenGL::V3_2;
+    let mut window: Window = WindowSettings::new(
+      "Pong".to_string(),
+      piston::window::Size {
+          width: arena.window_w as u32,
+          height: arena.window_h as u32
+      }
+    ).fullscreen(true).exit_on_esc(true).samples(4).build().unwrap();
+    let gl = match Gl::new(GlGraphics::new(opengl)) {
+        Ok(gl) => gl,
+        Err(e) => return println!("Couldn't load the font {}", e)
+    };
+    world.systems.draw.target = Some(Target::Gl(gl));
+
+    // Systems only ever see fixed size updates, however fast the event loop
+    // runs. Drawing interpolates between the last two of them.
+    let mut step = timestep::FixedStep::new(timestep::FIXED_DT);
+    let mut events = window.events();
+    while let Some(e) = events.next(&mut window) {
+        if let Some(update) = e.update_args() {
+            if let Some(ref mut session) = net {
+                session.poll();
+                world.data.services.latency = session.rtt;
+            }
+            let lost = net.as_ref().and_then(|session| match session.status {
+                net::Status::Disconnected(ref why) => Some(why.clone()),
+                net::Status::Playing => None
+            });
+            // Keep the window open to say why the game stopped.
+            if let Some(why) = lost {
+                world.data.services.state.stopped = Some(why);
+                world.data.services.latency = None;
+                net = None;
+            }
+            let steps = world.data.services.time.advance(&mut step, update.dt);
+            for _ in 0..steps {
+                if let Some(ref mut replay) = replay {
+                    replay.step(&mut world);
+                    continue;
+                }
+                if let Some(ref mut session) = net {
+                    let padding = world.data.services.arena.padding;
+                    session.sample(PaddleInput::sample(&world.data.services.input, &local_controller, padding));
+                    match session.advance() {
+                        Some(inputs) => world.data.services.net_input = Some(inputs),
+                        None => break // Waiting on the other player.
+                    }
+                }
+                *(world.data.services.event.borrow_mut()) = Event::Update(UpdateArgs { dt: step.dt });
+                world.update();
+                if let Some(ref mut recording) = recording {
+                    recording.tick(&mut world);
+                }
+                if let Some(ref mut session) = net {
+                    session.ran(&mut world);
+                }
+            }
+            if let Some(ref mut session) = net {
+                session.flush();
+            }
+            world.data.services.alpha = step.alpha();
+            continue;
+        }
+        // Both ends of a network game have to keep the same pace.
+        if let Some(Button::Keyboard(key)) = e.press_args() {
+            if local {
+                world.data.services.time.key(key);
+            }
+            world.data.services.debug.key(key);
+        }
+        if let Some(Button::Keyboard(keyboard::Key::F5)) = e.press_args() {
+            match Snapshot::capture(&mut world).save(SNAPSHOT_FILE) {
+                Ok(()) => println!("Saved {}", SNAPSHOT_FILE),
+                Err(err) => println!("Couldn't save snapshot {}", err)
+            }
+        }
+        if let Event::Input(_) = e {
+            if replay.is_some() {
+                continue;
+            }
+        }
+        if let Some(ref mut recording) = recording {
+            recording.input(&e);
+        }
+        *(world.data.services.event.borrow_mut()) = e;
+        world.update();
+    }
+
+    if let (Some(recording), Some(path)) = (recording, record) {
+        match recording.save(&path) {
+            Ok(()) => println!("Recorded {} ticks to {}", recording.ticks.len(), path),
+            Err(err) => println!("Couldn't save recording {}", err)
+        }
+    }
+    if let Some(mismatch) = replay.and_then(|r| r.mismatch) {
+        println!("Replay diverged at tick {}", mismatch.tick);
+    }
+    if let Some(ref mut session) = net {
+        if session.status == net::Status::Playing {
+            session.bye();
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use ecsrs::*;
+use rustc_serialize::json::{self, Json, Object};
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+use ecs::components::Side;
+use ecs::input::PaddleInput;
+use ecs::scaffold::Systems;
+use files;
+use replay::{self, Recording};
+
+/// Both ends must agree on this before a game starts.
+pub const PROTOCOL_VERSION: u64 = 2;
+/// Ticks between sampling local input and acting on it, 50ms at 120Hz. It
+/// hides that much latency before the game has to wait for the network.
+pub const DEFAULT_DELAY: u64 = 6;
+
+const TIMEOUT: f64 = 5.0; // Seconds of silence before giving up on the other player.
+const HANDSHAKE_TIMEOUT: f64 = 10.0;
+const WAIT_TIMEOUT: f64 = 120.0; // Seconds the host waits for someone to join.
+const HELLO_EVERY: u64 = 250; // Milliseconds between attempts to reach the host.
+const RESEND: usize = 64; // Most unacknowledged inputs sent in one packet.
+const HASH_EVERY: u64 = 120; // Ticks between comparing worlds.
+const MAX_PACKET: usize = 65536;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Status {
+    Playing,
+    Disconnected(String)
+}
+
+/// One end of a two player game played in lockstep. Each side sends the
+/// input for its own paddle a few ticks ahead, and a tick only runs once
+/// both inputs for it are in, so both worlds step identically from the
+/// snapshot the host sent at the start.
+pub struct Session {
+    socket: UdpSocket,
+    pub peer: SocketAddr,
+    pub side: Side, // Our paddle, the host plays left.
+    pub delay: u64,
+    pub tick: u64, // The next tick to run.
+    pub status: Status,
+    pub rtt: Option<f64>, // Seconds.
+    pub desync: Option<u64>, // First tick the worlds were seen to differ, which ends the game.
+    local: BTreeMap<u64, PaddleInput>, // Kept until run and acknowledged.
+    remote: BTreeMap<u64, PaddleInput>,
+    received: u64, // Remote input is in for every tick below this.
+    acked: u64, // The other side has our input for every tick below this.
+    hashes: BTreeMap<u64, u64>,
+    peer_hash: Option<(u64, u64)>,
+    clock: Instant,
+    last_heard: Instant,
+    ping: Option<f64>, // The other side's latest clock reading, to echo back.
+    welcome: Option<String>, // Sent again if the guest didn't get it.
+    buf: Vec<u8> // Packets are read into this.
+}
+
+fn seconds(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 * 1e-9
+}
+
+fn input_json(input: &PaddleInput) -> Json {
+    Json::Array(vec![
+        Json::Boolean(input.up),
+        Json::Boolean(input.down),
+        Json::F64(input.push),
+        input.mouse_y.map_or(Json::Null, Json::F64),
+        Json::Boolean(input.restart)
+    ])
+}
+
+fn input_from_json(json: &Json) -> Option<PaddleInput> {
+    json.as_array().and_then(|a| {
+        if a.len() != 5 {
+            return None;
+        }
+        Some(PaddleInput {
+            up: a[0].as_boolean().unwrap_or(false),
+            down: a[1].as_boolean().unwrap_or(false),
+            push: a[2].as_f64().unwrap_or(0.0),
+            mouse_y: a[3].as_f64(),
+            restart: a[4].as_boolean().unwrap_or(false)
+        })
+    })
+}
+
+fn message(fields: Vec<(&str, Json)>) -> String {
+    let o: Object = fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    format!("{}", json::as_json(&Json::Object(o)))
+}
+
+fn parse(buf: &[u8]) -> Option<Object> {
+    ::std::str::from_utf8(buf).ok()
+        .and_then(|text| files::parse_json(text).ok())
+        .and_then(|json| match json { Json::Object(o) => Some(o), _ => None })
+}
+
+impl Session {
+    fn new(socket: UdpSocket, peer: SocketAddr, side: Side, delay: u64, welcome: Option<String>) -> Session {
+        // Nobody can have sent input for the first few ticks, so they're empty.
+        let mut local = BTreeMap::new();
+        let mut remote = BTreeMap::new();
+        for t in 0..delay {
+            local.insert(t, PaddleInput::default());
+            remote.insert(t, PaddleInput::default());
+        }
+        let now = Instant::now();
+        Session {
+            socket: socket,
+            peer: peer,
+            side: side,
+            delay: delay,
+            tick: 0,
+            status: Status::Playing,
+            rtt: None,
+            desync: None,
+            local: local,
+            remote: remote,
+            received: delay,
+            acked: delay,
+            hashes: BTreeMap::new(),
+            peer_hash: None,
+            clock: now,
+            last_heard: now,
+            ping: None,
+            welcome: welcome,
+            buf: vec![0; MAX_PACKET]
+        }
+    }
+
+    /// Waits on `port` for a guest to say hello, then sends them `start` to
+    /// set up their world from. The host's own world must be set up from
+    /// `start.options()` too.
+    pub fn host(port: u16, start: &Recording, delay: u64) -> Result<Session, String> {
+        let socket = try!(UdpSocket::bind(("0.0.0.0", port)).map_err(|e| format!("port {}: {}", port, e)));
+        try!(socket.set_read_timeout(Some(Duration::from_millis(HELLO_EVERY))).map_err(|e| format!("{}", e)));
+        let started = Instant::now();
+        let mut buf = vec![0; MAX_PACKET];
+        let mut guest = None;
+        let mut refused = String::new(); // Anyone turned away for speaking another version.
+        while guest.is_none() {
+            if seconds(started.elapsed()) > WAIT_TIMEOUT {
+                return Err(format!("Nobody joined on port {} within {} seconds{}", port, WAIT_TIMEOUT, refused));
+            }
+            let (n, from) = match socket.recv_from(&mut buf) {
+                Ok(got) => got,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => continue,
+                Err(e) => return Err(format!("{}", e))
+            };
+            match parse(&buf[..n]).and_then(|o| o.get("hello").and_then(|v| v.as_u64())) {
+                Some(PROTOCOL_VERSION) => guest = Some(from),
+                Some(version) => refused = format!(", {} speaks version {} and we speak {}", from, version, PROTOCOL_VERSION),
+                None => ()
+            }
+        }
+        let peer = guest.unwrap();
+        let welcome = message(vec![
+            ("welcome", start.to_json()),
+            ("delay", Json::U64(delay))
+        ]);
+        try!(socket.send_to(welcome.as_bytes(), peer).map_err(|e| format!("{}", e)));
+        try!(socket.set_read_timeout(None).map_err(|e| format!("{}", e)));
+        try!(socket.set_nonblocking(true).map_err(|e| format!("{}", e)));
+        Ok(Session::new(socket, peer, Side::Left, delay, Some(welcome)))
+    }
+
+    /// Says hello to the host at `addr` until it answers with the game to
+    /// play, which the guest's world must be set up from.
+    pub fn join(addr: &str) -> Result<(Session, Recording), String> {
+        let peer = try!(try!(addr.to_socket_addrs().map_err(|e| format!("{}: {}", addr, e)))
+            .next().ok_or(format!("{}: no such address", addr)));
+        let socket = try!(UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| format!("{}", e)));
+        try!(socket.set_read_timeout(Some(Duration::from_millis(HELLO_EVERY))).map_err(|e| format!("{}", e)));
+        let hello = message(vec![("hello", Json::U64(PROTOCOL_VERSION))]);
+        let started = Instant::now();
+        let mut buf = vec![0; MAX_PACKET];
+        let mut answer = None;
+        while answer.is_none() {
+            if seconds(started.elapsed()) > HANDSHAKE_TIMEOUT {
+                return Err(format!("No answer from {}", peer));
+            }
+            try!(socket.send_to(hello.as_bytes(), peer).map_err(|e| format!("{}", e)));
+            let n = match socket.recv_from(&mut buf) {
+                Ok((n, from)) if from == peer => n,
+                _ => continue
+            };
+            let o = match parse(&buf[..n]) { Some(o) => o, None => continue };
+            if let (Some(welcome), Some(delay)) = (o.get("welcome"), o.get("delay").and_then(|d| d.as_u64())) {
+                answer = Some((try!(Recording::from_json(welcome)), delay));
+            }
+        }
+        let (start, delay) = answer.unwrap();
+        try!(socket.set_read_timeout(None).map_err(|e| format!("{}", e)));
+        try!(socket.set_nonblocking(true).map_err(|e| format!("{}", e)));
+        Ok((Session::new(socket, peer, Side::Right, delay, None), start))
+    }
+
+    fn now(&self) -> f64 {
+        seconds(self.clock.elapsed())
+    }
+
+    fn send(&mut self, text: &str) {
+        // Lost packets are made up for by the next one, so errors can wait
+        // for the timeout.
+        let _ = self.socket.send_to(text.as_bytes(), self.peer);
+    }
+
+    /// Reads everything that has arrived and notices if the other player
+    /// has gone quiet.
+    pub fn poll(&mut self) {
+        loop {
+            let (n, from) = match self.socket.recv_from(&mut self.buf) {
+                Ok(got) => got,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break // e.g. the other end's port closed, the timeout will catch it.
+            };
+            if from != self.peer {
+                continue;
+            }
+            if let Some(o) = parse(&self.buf[..n]) {
+                self.last_heard = Instant::now();
+                self.handle(o);
+            }
+        }
+        if self.status == Status::Playing && seconds(self.last_heard.elapsed()) > TIMEOUT {
+            self.status = Status::Disconnected(format!("Lost touch with {}", self.peer));
+        }
+    }
+
+    fn handle(&mut self, o: Object) {
+        if o.contains_key("hello") {
+            if let Some(welcome) = self.welcome.clone() {
+                self.send(&welcome);
+            }
+        }
+        if o.contains_key("bye") {
+            self.status = Status::Disconnected("The other player left".to_string());
+        }
+        if let (Some(from), Some(inputs)) = (o.get("from").and_then(|f| f.as_u64()), o.get("inputs").and_then(|i| i.as_array())) {
+            for (i, input) in inputs.iter().enumerate() {
+                let t = from + i as u64;
+                if t >= self.tick {
+                    if let Some(input) = input_from_json(input) {
+                        self.remote.entry(t).or_insert(input);
+                    }
+                }
+            }
+            while self.remote.contains_key(&self.received) {
+                self.received += 1;
+            }
+        }
+        if let Some(ack) = o.get("ack").and_then(|a| a.as_u64()) {
+            self.acked = cmp::max(self.acked, ack);
+        }
+        if let Some(ping) = o.get("ping").and_then(|p| p.as_f64()) {
+            self.ping = Some(ping);
+        }
+        if let Some(pong) = o.get("pong").and_then(|p| p.as_f64()) {
+            self.rtt = Some(self.now() - pong);
+        }
+        if let Some(hash) = o.get("hash").and_then(|h| h.as_array()) {
+            let tick = hash.get(0).and_then(|t| t.as_u64());
+            let hash = hash.get(1).and_then(|h| h.as_string()).and_then(|h| u64::from_str_radix(h, 16).ok());
+            if let (Some(tick), Some(hash)) = (tick, hash) {
+                self.peer_hash = Some((tick, hash));
+            }
+        }
+        self.compare();
+    }
+
+    fn compare(&mut self) {
+        if let Some((tick, theirs)) = self.peer_hash {
+            if let Some(&ours) = self.hashes.get(&tick) {
+                if ours != theirs && self.desync.is_none() {
+                    self.desync = Some(tick);
+                    self.status = Status::Disconnected(format!("Out of sync with {} since tick {}", self.peer, tick));
+                }
+            }
+        }
+    }
+
+    /// Queues our paddle's input for `delay` ticks from now.
+    pub fn sample(&mut self, input: PaddleInput) {
+        let t = self.tick + self.delay;
+        self.local.entry(t).or_insert(input);
+    }
+
+    /// Both paddles' input for the next tick, left then right, or None if
+    /// the other player's hasn't arrived yet.
+    pub fn advance(&mut self) -> Option<[PaddleInput; 2]> {
+        let (ours, theirs) = match (self.local.get(&self.tick), self.remote.get(&self.tick)) {
+            (Some(ours), Some(theirs)) => (*ours, *theirs),
+            _ => return None
+        };
+        self.remote.remove(&self.tick);
+        self.tick += 1;
+        let (tick, acked) = (self.tick, self.acked);
+        self.local = self.local.iter()
+            .filter(|&(t, _)| *t >= cmp::min(tick, acked))
+            .map(|(t, i)| (*t, *i))
+            .collect();
+        Some(match self.side {
+            Side::Left => [ours, theirs],
+            Side::Right => [theirs, ours]
+        })
+    }
+
+    /// Call once a tick from `advance` has run, so the worlds can be compared.
+    pub fn ran(&mut self, world: &mut World<Systems>) {
+        if self.tick % HASH_EVERY == 0 {
+            self.hashes.insert(self.tick, replay::world_hash(world));
+            while self.hashes.len() > 16 {
+                let oldest = *self.hashes.keys().next().unwrap();
+                self.hashes.remove(&oldest);
+            }
+            self.compare();
+        }
+    }
+
+    /// Sends whatever input the other side hasn't acknowledged, once a frame.
+    pub fn flush(&mut self) {
+        let acked = self.acked;
+        let pending: Vec<(u64, Json)> = self.local.iter()
+            .filter(|&(t, _)| *t >= acked)
+            .take(RESEND)
+            .map(|(t, i)| (*t, input_json(i)))
+            .collect();
+        let mut fields = vec![
+            ("ack", Json::U64(self.received)),
+            ("ping", Json::F64(self.now()))
+        ];
+        if let Some(&(from, _)) = pending.first() {
+            fields.push(("from", Json::U64(from)));
+            fields.push(("inputs", Json::Array(pending.into_iter().map(|(_, i)| i).collect())));
+        }
+        if let Some(ping) = self.ping {
+            fields.push(("pong", Json::F64(ping)));
+        }
+        if let Some((&tick, &hash)) = self.hashes.iter().next_back() {
+            fields.push(("hash", Json::Array(vec![Json::U64(tick), Json::String(format!("{:016x}", hash))])));
+        }
+        let text = message(fields);
+        self.send(&text);
+    }
+
+    /// Tells the other player we're going, rather than leaving them to time out.
+    pub fn bye(&mut self) {
+        let bye = message(vec![("bye", Json::Boolean(true))]);
+        self.send(&bye);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let peer = socket.local_addr().unwrap();
+        Session::new(socket, peer, Side::Left, DEFAULT_DELAY, None)
+    }
+
+    #[test]
+    fn differing_worlds_end_the_game() {
+        let mut session = session();
+        session.hashes.insert(120, 1);
+        session.peer_hash = Some((120, 1));
+        session.compare();
+        assert_eq!(session.status, Status::Playing);
+        session.hashes.insert(240, 2);
+        session.peer_hash = Some((240, 3));
+        session.compare();
+        assert_eq!(session.desync, Some(240));
+        match session.status {
+            Status::Disconnected(ref why) => assert!(why.contains("since tick 240"), "{}", why),
+            Status::Playing => panic!("still playing")
+        }
+    }
+}
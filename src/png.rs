@@ -0,0 +1,121 @@
+// Just enough PNG to save RGBA screenshots: no compression, so nothing to
+// depend on, and the same pixels always give the same bytes.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const MAX_STORED: usize = 65535; // Largest deflate block that isn't compressed.
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for n in 0..256 {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+        }
+        table[n] = c;
+    }
+    !bytes.iter().fold(!0u32, |c, b| table[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn be32(v: u32) -> [u8; 4] {
+    [(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&be32(data.len() as u32));
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&be32(crc));
+}
+
+// A zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(MAX_STORED).collect() };
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i + 1 == blocks.len();
+        let len = block.len() as u16;
+        out.push(if last { 1 } else { 0 });
+        out.extend_from_slice(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&be32(adler32(data)));
+    out
+}
+
+/// `rgba` holds `height` rows of `width` pixels, top row first.
+pub fn encode(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let row = width as usize * 4;
+    assert_eq!(rgba.len(), row * height as usize);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&be32(width));
+    header.extend_from_slice(&be32(height));
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bit RGBA, no interlacing.
+
+    // Every row starts with its filter, which is always none.
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in rgba.chunks(row) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+pub fn save<P: AsRef<Path>>(path: P, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    let path = path.as_ref();
+    File::create(path)
+        .and_then(|mut f| f.write_all(&encode(width, height, rgba)))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn large_data_is_split_into_stored_blocks() {
+        let data = vec![7u8; MAX_STORED + 10];
+        let z = zlib_stored(&data);
+        assert_eq!(z.len(), 2 + 5 + MAX_STORED + 5 + 10 + 4);
+        assert_eq!(&z[2..7], &[0, 0xff, 0xff, 0, 0]);
+        assert_eq!(&z[7 + MAX_STORED..12 + MAX_STORED], &[1, 10, 0, 0xf5, 0xff]);
+        assert_eq!(&z[z.len() - 4..], &be32(adler32(&data)));
+    }
+
+    #[test]
+    fn encoded_layout() {
+        let png = encode(1, 1, &[255, 0, 0, 255]);
+        assert_eq!(&png[..8], &SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+    }
+}
@@ -0,0 +1,289 @@
+use ecsrs::*;
+use piston::input::{Button, ControllerAxisArgs, ControllerButton, Event, Input, Motion, UpdateArgs};
+use rustc_serialize::json::{self, Json, Object};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use ecs::components::Binding;
+use ecs::keys;
+use ecs::prefab::{Prefab, Prefabs};
+use ecs::scaffold::Systems;
+use ecs::snapshot::Snapshot;
+use files;
+use Options;
+
+/// Bumped whenever the file layout changes.
+pub const REPLAY_VERSION: u64 = 1;
+
+/// The input that arrived before one fixed update, and a hash of the world
+/// straight after it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tick {
+    pub input: Vec<Input>,
+    pub hash: u64
+}
+
+/// A game from its first update: where it started, and every tick since.
+/// Together with the rng saved in `start` that is all it takes to play the
+/// same game again.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recording {
+    pub dt: f64,
+    pub start: Snapshot,
+    pub prefabs: Prefabs, // For balls served after the start.
+    pub ticks: Vec<Tick>,
+    pending: Vec<Input>
+}
+
+/// The first tick whose world differed from the recording.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Mismatch {
+    pub tick: usize,
+    pub expected: u64,
+    pub actual: u64
+}
+
+/// FNV-1a over the world's snapshot. Only meant for comparing worlds from
+/// the same build, but unlike `Hash` it won't change between Rust releases.
+pub fn world_hash(world: &mut World<Systems>) -> u64 {
+    let text = format!("{}", Snapshot::capture(world).to_json());
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn input_json(input: &Input) -> Option<Json> {
+    let (kind, value) = match *input {
+        Input::Press(Button::Keyboard(key)) => ("press_key", Json::String(keys::key_name(key))),
+        Input::Release(Button::Keyboard(key)) => ("release_key", Json::String(keys::key_name(key))),
+        Input::Press(Button::Mouse(button)) => ("press_mouse", Json::String(keys::mouse_button_name(button))),
+        Input::Release(Button::Mouse(button)) => ("release_mouse", Json::String(keys::mouse_button_name(button))),
+        Input::Press(Button::Controller(pad)) => ("press_pad", Json::String(Binding::Pad { id: pad.id, button: pad.button }.name())),
+        Input::Release(Button::Controller(pad)) => ("release_pad", Json::String(Binding::Pad { id: pad.id, button: pad.button }.name())),
+        Input::Move(Motion::MouseCursor(x, y)) => ("mouse", Json::Array(vec![Json::F64(x), Json::F64(y)])),
+        Input::Move(Motion::ControllerAxis(ref args)) => ("pad_axis", Json::Array(vec![
+            Json::I64(args.id as i64), Json::U64(args.axis as u64), Json::F64(args.position)
+        ])),
+        Input::Focus(focused) => ("focus", Json::Boolean(focused)), // Losing it lets go of every key.
+        _ => return None // Nothing in the game reacts to it.
+    };
+    let mut o = Object::new();
+    o.insert(kind.to_string(), value);
+    Some(Json::Object(o))
+}
+
+fn pad_button(name: &str) -> Result<ControllerButton, String> {
+    match try!(name.parse()) {
+        Binding::Pad { id, button } => Ok(ControllerButton { id: id, button: button }),
+        _ => Err(format!("'{}' isn't a pad button", name))
+    }
+}
+
+fn input_from_json(json: &Json) -> Result<Input, String> {
+    let o = try!(json.as_object().ok_or("Recorded input should be an object".to_string()));
+    let (kind, value) = try!(o.iter().next().ok_or("Recorded input is empty".to_string()));
+    let name = || value.as_string().ok_or(format!("Recorded '{}' should be a string", kind));
+    Ok(match &kind[..] {
+        "press_key" => Input::Press(Button::Keyboard(try!(name().and_then(keys::key_from_name)))),
+        "release_key" => Input::Release(Button::Keyboard(try!(name().and_then(keys::key_from_name)))),
+        "press_mouse" => Input::Press(Button::Mouse(try!(name().and_then(keys::mouse_button_from_name)))),
+        "release_mouse" => Input::Release(Button::Mouse(try!(name().and_then(keys::mouse_button_from_name)))),
+        "press_pad" => Input::Press(Button::Controller(try!(name().and_then(pad_button)))),
+        "release_pad" => Input::Release(Button::Controller(try!(name().and_then(pad_button)))),
+        "pad_axis" => {
+            let v: Vec<f64> = value.as_array().map(|a| a.iter().filter_map(|v| v.as_f64()).collect()).unwrap_or(Vec::new());
+            if v.len() != 3 {
+                return Err("Recorded 'pad_axis' should be an id, axis and position".to_string());
+            }
+            Input::Move(Motion::ControllerAxis(ControllerAxisArgs { id: v[0] as i32, axis: v[1] as u8, position: v[2] }))
+        },
+        "focus" => Input::Focus(try!(value.as_boolean().ok_or("Recorded 'focus' should be true or false".to_string()))),
+        "mouse" => {
+            let xy: Vec<f64> = value.as_array().map(|a| a.iter().filter_map(|v| v.as_f64()).collect()).unwrap_or(Vec::new());
+            if xy.len() != 2 {
+                return Err("Recorded 'mouse' should be 2 numbers".to_string());
+            }
+            Input::Move(Motion::MouseCursor(xy[0], xy[1]))
+        },
+        _ => return Err(format!("Unknown recorded input '{}'", kind))
+    })
+}
+
+fn get<'a>(o: &'a Object, key: &str) -> Result<&'a Json, String> {
+    o.get(key).ok_or_else(|| format!("Recording is missing '{}'", key))
+}
+
+impl Recording {
+    /// Starts recording a game set up from `options`. The world to record
+    /// has to be set up with `options()` so it matches the replay exactly.
+    pub fn new(dt: f64, options: &Options) -> Recording {
+        let mut scratch = World::<Systems>::new();
+        ::setup(&mut scratch, options);
+        Recording {
+            dt: dt,
+            start: Snapshot::capture(&mut scratch),
+            prefabs: options.prefabs.clone(),
+            ticks: Vec::new(),
+            pending: Vec::new()
+        }
+    }
+
+    /// Options that set up a world the same way the replay will, from the
+    /// starting snapshot rather than freshly spawned entities.
+    pub fn options(&self) -> Options {
+        Options {
+            seed: Some(self.start.seed),
+            arena: self.start.arena.clone(),
+            prefabs: self.prefabs.clone(),
+            snapshot: Some(self.start.clone()),
+            ..Options::default()
+        }
+    }
+
+    /// Remembers input that the next update will see the effects of.
+    pub fn input(&mut self, event: &Event) {
+        if let Event::Input(ref input) = *event {
+            if input_json(input).is_some() {
+                self.pending.push(input.clone());
+            }
+        }
+    }
+
+    /// Call straight after each fixed update.
+    pub fn tick(&mut self, world: &mut World<Systems>) {
+        let input = self.pending.drain(..).collect();
+        self.ticks.push(Tick { input: input, hash: world_hash(world) });
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut prefabs = Object::new();
+        prefabs.insert("ball".to_string(), self.prefabs.ball.to_json());
+        prefabs.insert("left_paddle".to_string(), self.prefabs.left_paddle.to_json());
+        prefabs.insert("right_paddle".to_string(), self.prefabs.right_paddle.to_json());
+
+        let ticks = self.ticks.iter().map(|tick| {
+            let mut t = Object::new();
+            // As a string, json numbers don't survive the full 64 bits.
+            t.insert("hash".to_string(), Json::String(format!("{:016x}", tick.hash)));
+            if !tick.input.is_empty() {
+                t.insert("input".to_string(), Json::Array(tick.input.iter().filter_map(input_json).collect()));
+            }
+            Json::Object(t)
+        }).collect();
+
+        let mut o = Object::new();
+        o.insert("version".to_string(), Json::U64(REPLAY_VERSION));
+        o.insert("dt".to_string(), Json::F64(self.dt));
+        o.insert("start".to_string(), self.start.to_json());
+        o.insert("prefabs".to_string(), Json::Object(prefabs));
+        o.insert("ticks".to_string(), Json::Array(ticks));
+        Json::Object(o)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Recording, String> {
+        let o = try!(json.as_object().ok_or("A recording should be an object".to_string()));
+        let version = try!(try!(get(o, "version")).as_u64().ok_or("Recording 'version' should be a whole number".to_string()));
+        if version != REPLAY_VERSION {
+            return Err(format!("Recording is version {}, this build reads version {}", version, REPLAY_VERSION));
+        }
+        let dt = try!(try!(get(o, "dt")).as_f64().ok_or("Recording 'dt' should be a number".to_string()));
+        let start = try!(Snapshot::from_json(try!(get(o, "start"))));
+
+        let p = try!(try!(get(o, "prefabs")).as_object().ok_or("Recording 'prefabs' should be an object".to_string()));
+        let prefab = |key: &str| get(p, key).and_then(Prefab::from_json).map_err(|e| format!("{}: {}", key, e));
+        let prefabs = Prefabs {
+            ball: try!(prefab("ball")),
+            left_paddle: try!(prefab("left_paddle")),
+            right_paddle: try!(prefab("right_paddle"))
+        };
+
+        let list = try!(try!(get(o, "ticks")).as_array().ok_or("Recording 'ticks' should be a list".to_string()));
+        let mut ticks = Vec::new();
+        for (i, t) in list.iter().enumerate() {
+            let t = try!(t.as_object().ok_or(format!("tick {} should be an object", i)));
+            let hash = try!(get(t, "hash")
+                .and_then(|h| h.as_string().ok_or("'hash' should be a string".to_string()))
+                .and_then(|h| u64::from_str_radix(h, 16).map_err(|e| format!("{}", e)))
+                .map_err(|e| format!("tick {}: {}", i, e)));
+            let mut input = Vec::new();
+            if let Some(list) = t.get("input").and_then(|i| i.as_array()) {
+                for json in list {
+                    input.push(try!(input_from_json(json).map_err(|e| format!("tick {}: {}", i, e))));
+                }
+            }
+            ticks.push(Tick { input: input, hash: hash });
+        }
+
+        Ok(Recording {
+            dt: dt,
+            start: start,
+            prefabs: prefabs,
+            ticks: ticks,
+            pending: Vec::new()
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = format!("{}", json::as_json(&self.to_json()));
+        File::create(path)
+            .and_then(|mut f| f.write_all(text.as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Recording, String> {
+        files::load(path.as_ref(), |src| files::parse_json(src).and_then(|json| Recording::from_json(&json)))
+    }
+}
+
+/// Feeds a recording back into a world, one tick at a time.
+pub struct Replay {
+    pub recording: Recording,
+    pub tick: usize,
+    pub mismatch: Option<Mismatch> // The first, if any.
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Replay {
+        Replay { recording: recording, tick: 0, mismatch: None }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.tick >= self.recording.ticks.len()
+    }
+
+    /// Plays the next tick's input and update into `world`, which must have
+    /// been set up with the recording's `options`. Returns false once
+    /// there is nothing left to play.
+    pub fn step(&mut self, world: &mut World<Systems>) -> bool {
+        let tick = match self.recording.ticks.get(self.tick) {
+            Some(tick) => tick.clone(),
+            None => return false
+        };
+        for input in tick.input {
+            *(world.data.services.event.borrow_mut()) = Event::Input(input);
+            world.update();
+        }
+        *(world.data.services.event.borrow_mut()) = Event::Update(UpdateArgs { dt: self.recording.dt });
+        world.update();
+        if self.mismatch.is_none() {
+            let actual = world_hash(world);
+            if actual != tick.hash {
+                self.mismatch = Some(Mismatch { tick: self.tick, expected: tick.hash, actual: actual });
+            }
+        }
+        self.tick += 1;
+        true
+    }
+
+    /// Replays the whole recording without a window, stopping at the first
+    /// tick that differs if `verify` is set.
+    pub fn run(&mut self, verify: bool) -> World<Systems> {
+        let mut world = World::<Systems>::new();
+        ::setup(&mut world, &self.recording.options());
+        while self.step(&mut world) {
+            if verify && self.mismatch.is_some() {
+                break;
+            }
+        }
+        world
+    }
+}
@@ -0,0 +1,177 @@
+use piston::input::keyboard::Key;
+
+/// Simulation rate. Every Update event the systems see carries exactly this dt.
+pub const FIXED_DT: f64 = 1.0 / 120.0;
+
+// After a long stall (dragging the window, a breakpoint) drop the backlog
+// rather than trying to catch up on seconds of simulation at once.
+const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// Turns variable frame times into a whole number of fixed size steps.
+pub struct FixedStep {
+    pub dt: f64,
+    accumulator: f64
+}
+
+impl FixedStep {
+    pub fn new(dt: f64) -> FixedStep {
+        FixedStep {
+            dt: dt,
+            accumulator: 0.0
+        }
+    }
+
+    /// Adds `elapsed` seconds and returns how many steps are now due.
+    pub fn advance(&mut self, elapsed: f64) -> u32 {
+        self.accumulator += elapsed;
+        let mut steps = 0;
+        while self.accumulator >= self.dt {
+            self.accumulator -= self.dt;
+            steps += 1;
+        }
+        if steps > MAX_STEPS_PER_FRAME {
+            steps = MAX_STEPS_PER_FRAME;
+        }
+        steps
+    }
+
+    /// How far between the last step and the next one we are, from 0 to 1.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.dt
+    }
+}
+
+/// Slow motion and fast forward speeds, as multiples of real time. Past 4x a
+/// 60Hz frame would need more steps than `MAX_STEPS_PER_FRAME`.
+pub const SCALES: &'static [f64] = &[0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// Debug control over how fast the game runs. It only changes how many fixed
+/// steps are due each frame, never their dt, so the same ticks happen in the
+/// same order at any speed and recordings made in slow motion still replay.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TimeControl {
+    pub paused: bool,
+    pub scale: f64,
+    steps: u32 // Asked for one by one while paused.
+}
+
+impl Default for TimeControl {
+    fn default() -> TimeControl {
+        TimeControl { paused: false, scale: 1.0, steps: 0 }
+    }
+}
+
+impl TimeControl {
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.steps = 0;
+    }
+
+    /// Runs exactly one step on the next frame, pausing first if need be.
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.steps += 1;
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale.max(SCALES[0]).min(SCALES[SCALES.len() - 1]);
+    }
+
+    pub fn slower(&mut self) {
+        if let Some(&scale) = SCALES.iter().rev().find(|&&s| s < self.scale) {
+            self.scale = scale;
+        }
+    }
+
+    pub fn faster(&mut self) {
+        if let Some(&scale) = SCALES.iter().find(|&&s| s > self.scale) {
+            self.scale = scale;
+        }
+    }
+
+    /// F6 pauses, F7 steps, F8 and F9 slow down and speed up. Returns whether
+    /// `key` was one of them.
+    pub fn key(&mut self, key: Key) -> bool {
+        match key {
+            Key::F6 => self.toggle_pause(),
+            Key::F7 => self.step(),
+            Key::F8 => self.slower(),
+            Key::F9 => self.faster(),
+            _ => return false
+        }
+        true
+    }
+
+    /// Like `FixedStep::advance`, but with `elapsed` scaled, or only the
+    /// steps asked for while paused.
+    pub fn advance(&mut self, fixed: &mut FixedStep, elapsed: f64) -> u32 {
+        if self.paused {
+            let steps = self.steps;
+            self.steps = 0;
+            return steps;
+        }
+        fixed.advance(elapsed * self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_step_keeps_the_remainder() {
+        let mut fixed = FixedStep::new(0.25);
+        assert_eq!(fixed.advance(0.625), 2);
+        assert_eq!(fixed.alpha(), 0.5);
+        assert_eq!(fixed.advance(0.125), 1);
+        assert_eq!(fixed.advance(0.125), 0);
+    }
+
+    #[test]
+    fn fixed_step_drops_a_backlog() {
+        let mut fixed = FixedStep::new(0.25);
+        assert_eq!(fixed.advance(10.0), MAX_STEPS_PER_FRAME);
+        assert_eq!(fixed.advance(0.0), 0);
+    }
+
+    #[test]
+    fn paused_time_only_runs_the_steps_asked_for() {
+        let mut fixed = FixedStep::new(0.25);
+        let mut time = TimeControl::default();
+        time.toggle_pause();
+        assert_eq!(time.advance(&mut fixed, 1.0), 0);
+        time.step();
+        time.step();
+        assert_eq!(time.advance(&mut fixed, 1.0), 2);
+        assert_eq!(time.advance(&mut fixed, 1.0), 0);
+        time.toggle_pause();
+        assert_eq!(time.advance(&mut fixed, 1.0), 4);
+    }
+
+    #[test]
+    fn step_pauses() {
+        let mut time = TimeControl::default();
+        assert!(time.key(Key::F7));
+        assert!(time.paused);
+        assert!(!time.key(Key::F1));
+    }
+
+    #[test]
+    fn scale() {
+        let mut fixed = FixedStep::new(0.25);
+        let mut time = TimeControl::default();
+        time.slower();
+        assert_eq!(time.scale, 0.5);
+        assert_eq!(time.advance(&mut fixed, 1.0), 2);
+        for _ in 0..10 {
+            time.faster();
+        }
+        assert_eq!(time.scale, 4.0);
+        for _ in 0..10 {
+            time.slower();
+        }
+        assert_eq!(time.scale, 0.1);
+        time.set_scale(100.0);
+        assert_eq!(time.scale, 4.0);
+    }
+}
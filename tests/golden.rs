@@ -0,0 +1,55 @@
+// Draws frames of seeded headless games with the software rasterizer and
+// compares them byte for byte with the PNGs in assets/golden, so a change in
+// what ends up on screen fails the tests. Look at the .actual.png files left
+// next to any that differ, and if they're right rerun with GOLDEN_BLESS=1 to
+// replace the old ones:
+//
+//     GOLDEN_BLESS=1 cargo test --test golden
+
+extern crate rustecspong;
+
+use rustecspong::Options;
+use rustecspong::headless::{self, Headless};
+use rustecspong::png;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Seed and ticks to run before each frame.
+const FRAMES: &'static [(u64, u64)] = &[(1, 0), (1, 120), (7, 600), (42, 2400)];
+
+#[test]
+fn frames_match_goldens() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/golden");
+    let bless = env::var("GOLDEN_BLESS").map(|v| v == "1").unwrap_or(false);
+    if bless {
+        std::fs::create_dir_all(&dir).unwrap();
+    }
+    let mut failed = Vec::new();
+    for &(seed, ticks) in FRAMES {
+        let options = Options { seed: Some(seed), ..Options::default() };
+        let mut sim = Headless::new(headless::DEFAULT_DT, &options);
+        sim.run(ticks);
+        let frame = sim.screenshot();
+        let path = dir.join(format!("seed{}-tick{}.png", seed, ticks));
+        if bless {
+            frame.save(&path).unwrap();
+            continue;
+        }
+        let mut golden = Vec::new();
+        let same = File::open(&path)
+            .and_then(|mut f| f.read_to_end(&mut golden))
+            .map(|_| golden == png::encode(frame.width, frame.height, &frame.pixels));
+        match same {
+            Ok(true) => (),
+            Ok(false) => {
+                let actual = path.with_extension("actual.png");
+                frame.save(&actual).unwrap();
+                failed.push(format!("{} differs, see {}", path.display(), actual.display()));
+            }
+            Err(e) => failed.push(format!("{}: {}, bless with GOLDEN_BLESS=1", path.display(), e))
+        }
+    }
+    assert!(failed.is_empty(), "\n{}", failed.join("\n"));
+}
@@ -0,0 +1,38 @@
+// A game saved mid rally and loaded into another world has to carry on
+// exactly as the original does.
+
+extern crate rustecspong;
+
+use rustecspong::Options;
+use rustecspong::ecs::snapshot::Snapshot;
+use rustecspong::headless::{self, Headless};
+use rustecspong::replay::world_hash;
+
+#[test]
+fn save_restore_step_matches() {
+    let options = Options { seed: Some(7), ..Options::default() };
+    let mut original = Headless::new(headless::DEFAULT_DT, &options);
+    while original.world.data.services.rally == 0 {
+        assert!(original.ticks < 10000, "no paddle hit the ball");
+        original.step();
+    }
+    original.run(30);
+
+    let path = std::env::temp_dir().join("rustecspong-snapshot-test.json");
+    Snapshot::capture(&mut original.world).save(&path).unwrap();
+    let saved = Snapshot::load(&path);
+    let _ = std::fs::remove_file(&path);
+    let saved = saved.unwrap();
+
+    let other = Options { seed: Some(99), ..Options::default() };
+    let mut restored = Headless::new(headless::DEFAULT_DT, &other);
+    saved.restore(&mut restored.world);
+    assert_eq!(restored.world.data.services.rally, original.world.data.services.rally);
+    assert_eq!(world_hash(&mut restored.world), world_hash(&mut original.world));
+
+    for tick in 0..600 {
+        original.step();
+        restored.step();
+        assert_eq!(world_hash(&mut restored.world), world_hash(&mut original.world), "diverged {} ticks after restoring", tick + 1);
+    }
+}